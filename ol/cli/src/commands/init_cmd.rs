@@ -8,6 +8,7 @@ use anyhow::Error;
 use diem_genesis_tool::{init, key};
 use diem_types::waypoint::Waypoint;
 use ol_keys::{scheme::KeyScheme, wallet};
+use ol_types::key_store_checksum;
 use diem_json_rpc_client::AccountAddress;
 use diem_types::transaction::authenticator::AuthenticationKey;
 use std::{fs, path::PathBuf};
@@ -102,7 +103,7 @@ pub fn initialize_app_cfg(
     wp_opt: &Option<Waypoint>,
     source_path: &Option<PathBuf>
 ) -> Result <AppCfg, Error>{
-    let cfg = AppCfg::init_app_configs(
+    AppCfg::init_app_configs(
         authkey,
         account,
         upstream_peer,
@@ -112,8 +113,7 @@ pub fn initialize_app_cfg(
         source_path,
         None,
         None,
-    );
-    Ok(cfg)
+    )
 }
 
 /// Initializes the necessary 0L config files: 0L.toml and populate blocks directory
@@ -149,17 +149,57 @@ pub fn initialize_host_swarm(
 /// Initializes the necessary validator config files: genesis.blob, key_store.json
 pub fn initialize_validator(
     wallet: &WalletLibrary, miner_config: &AppCfg, way_opt: Option<Waypoint>, is_genesis: bool
+) -> Result <(), Error>{
+    initialize_validator_keys(KeyScheme::new(wallet), miner_config, way_opt, is_genesis, None)
+}
+
+/// A step of the key store initialization, reported to progress callbacks before it runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitStep {
+    /// all keys of the KeyScheme
+    KeyStore,
+    /// operator account, from the operator key
+    OperatorKey,
+    /// owner account
+    OwnerKey,
+    /// genesis waypoint
+    GenesisWaypoint,
+    /// waypoint
+    Waypoint,
+}
+
+/// Called with each step of the key store initialization and the namespace it's written to
+pub type InitProgress<'a> = &'a dyn Fn(InitStep, &str);
+
+/// Initializes the validator key_store.json from the keys of a KeyScheme, e.g. of a non-default account index.
+/// Each step is reported to the progress callback, if any, before it runs. The checksum of the keys is
+/// written next to it.
+pub fn initialize_validator_keys(
+    keys: KeyScheme,
+    miner_config: &AppCfg,
+    way_opt: Option<Waypoint>,
+    is_genesis: bool,
+    progress: Option<InitProgress>,
 ) -> Result <(), Error>{
     let home_dir = &miner_config.workspace.node_home;
-    let keys = KeyScheme::new(wallet);
     let namespace = miner_config.profile.account.to_hex(); // same format as serializer for 0L/toml
+    let report = |step: InitStep| {
+        if let Some(f) = progress { f(step, &namespace) }
+    };
+
+    report(InitStep::KeyStore);
     init::key_store_init(home_dir, &namespace, keys, is_genesis);
+    report(InitStep::OperatorKey);
     key::set_operator_key(home_dir, &namespace);
+    report(InitStep::OwnerKey);
     key::set_owner_key(home_dir, &namespace);
     if let Some(way) = way_opt {
+        report(InitStep::GenesisWaypoint);
         key::set_genesis_waypoint(home_dir, &namespace, way);
+        report(InitStep::Waypoint);
         key::set_waypoint(home_dir, &namespace, way);
     }    
+    key_store_checksum::write_checksum(home_dir)?;
 
     Ok(())
 }
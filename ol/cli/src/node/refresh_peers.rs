@@ -37,7 +37,7 @@ impl Node {
 
                 if url_list.len() > 0 {
                     cfg.profile.upstream_nodes = Some(url_list);
-                    cfg.save_file();
+                    cfg.save_file()?;
                 }
 
                 return Ok(());
@@ -36,4 +36,16 @@ pub fn get_demo_autopay_json() -> (String, PathBuf) {
 pub fn get_test_genesis_blob() -> PathBuf {
   let path= env!("CARGO_MANIFEST_DIR");
   Path::new(path).join("genesis").join("swarm_genesis.blob")
+}
+
+/// genesis.blob and genesis waypoint of a network, bundled in the binary, by chain id
+pub fn get_bundled_genesis(chain_id: u8) -> Option<(&'static [u8], &'static str)> {
+  match chain_id {
+    // mainnet
+    1 => Some((
+      include_bytes!("../../../genesis.blob"),
+      include_str!("../../../genesis_waypoint"),
+    )),
+    _ => None,
+  }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 //! Key derivation for 0L.
 
+use diem_crypto::ed25519::Ed25519PublicKey;
 use diem_types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey};
 use diem_wallet::{
     key_factory::{ChildNumber, ExtendedPrivKey},
@@ -21,19 +22,45 @@ pub struct KeyScheme {
     pub child_4_consensus: ExtendedPrivKey,
     /// Execution key
     pub child_5_executor: ExtendedPrivKey,
+    /// Account index the keys were derived at
+    pub account_index: u64,
 }
 
+/// A key of the scheme, the role it has on chain, and where it was derived from
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyDerivation {
+    /// role of the key, as named in the key store
+    pub role: &'static str,
+    /// child number of the key in the wallet
+    pub child_number: u64,
+    /// public key of the derived child
+    pub public_key: Ed25519PublicKey,
+}
+
+/// Number of child keys derived for each account of the KeyScheme.
+pub const KEYS_PER_ACCOUNT: u64 = 6;
+
+/// Version of the derivation scheme, bump when the child key layout changes.
+pub const SCHEME_VERSION: u64 = 1;
+
 impl KeyScheme {
     /// Generates the necessary private keys for validator and full node set up.
     pub fn new(wallet: &WalletLibrary) -> Self {
+        KeyScheme::new_with_index(wallet, 0)
+    }
+    /// Generates the keys of the account at a derivation index. Each index uses the next
+    /// KEYS_PER_ACCOUNT children, index 0 is the default account.
+    pub fn new_with_index(wallet: &WalletLibrary, index: u64) -> Self {
         let kf = wallet.get_key_factory();
+        let base = index * KEYS_PER_ACCOUNT;
         Self {
-            child_0_owner: kf.private_child(ChildNumber::new(0)).unwrap(),
-            child_1_operator: kf.private_child(ChildNumber::new(1)).unwrap(),
-            child_2_val_network: kf.private_child(ChildNumber::new(2)).unwrap(),
-            child_3_fullnode_network: kf.private_child(ChildNumber::new(3)).unwrap(),
-            child_4_consensus: kf.private_child(ChildNumber::new(4)).unwrap(),
-            child_5_executor: kf.private_child(ChildNumber::new(5)).unwrap(),
+            child_0_owner: kf.private_child(ChildNumber::new(base)).unwrap(),
+            child_1_operator: kf.private_child(ChildNumber::new(base + 1)).unwrap(),
+            child_2_val_network: kf.private_child(ChildNumber::new(base + 2)).unwrap(),
+            child_3_fullnode_network: kf.private_child(ChildNumber::new(base + 3)).unwrap(),
+            child_4_consensus: kf.private_child(ChildNumber::new(base + 4)).unwrap(),
+            child_5_executor: kf.private_child(ChildNumber::new(base + 5)).unwrap(),
+            account_index: index,
         }
     }
     /// Get KeyScheme from a mnemonic string.
@@ -43,7 +70,89 @@ impl KeyScheme {
     }
     /// Returns the default owner address given the key derivation.
     pub fn derived_address(&self) -> AccountAddress {
-        let staged_owner_auth_key = AuthenticationKey::ed25519(&self.child_0_owner.get_public());
-        staged_owner_auth_key.derived_address()
+        self.derived_auth_key().derived_address()
+    }
+    /// Returns the owner authentication key given the key derivation.
+    pub fn derived_auth_key(&self) -> AuthenticationKey {
+        auth_key_from_public(&self.child_0_owner.get_public())
     }
+    /// Each key with the name of its role, in child number order
+    fn roles(&self) -> Vec<(&'static str, &ExtendedPrivKey)> {
+        vec![
+            ("owner", &self.child_0_owner),
+            ("operator", &self.child_1_operator),
+            ("validator_network", &self.child_2_val_network),
+            ("fullnode_network", &self.child_3_fullnode_network),
+            ("consensus", &self.child_4_consensus),
+            ("execution", &self.child_5_executor),
+        ]
+    }
+    /// Private key bytes of each role, to check they don't end up in shared files.
+    pub fn private_key_bytes(&self) -> Vec<(&'static str, Vec<u8>)> {
+        self.roles()
+            .into_iter()
+            .map(|(role, key)| (role, key.get_private_key().to_bytes().to_vec()))
+            .collect()
+    }
+    /// Each key role with its derivation, in child number order.
+    pub fn derivations(&self) -> Vec<KeyDerivation> {
+        let base = self.account_index * KEYS_PER_ACCOUNT;
+        self.roles()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (role, key))| KeyDerivation {
+                role,
+                child_number: base + i as u64,
+                public_key: key.get_public(),
+            })
+            .collect()
+    }
+}
+
+/// The authentication key of an owner public key, the account address is derived from it.
+/// For when only the public key of an account is known, not its mnemonic.
+pub fn auth_key_from_public(public_key: &Ed25519PublicKey) -> AuthenticationKey {
+    AuthenticationKey::ed25519(public_key)
+}
+
+#[test]
+fn account_index() {
+    let mut wallet = WalletLibrary::new();
+    let (auth_key, _) = wallet.new_address().unwrap();
+
+    let default_keys = KeyScheme::new_with_index(&wallet, 0);
+    assert_eq!(default_keys.derived_auth_key(), auth_key);
+    assert_eq!(default_keys.derived_address(), KeyScheme::new(&wallet).derived_address());
+
+    let next_keys = KeyScheme::new_with_index(&wallet, 1);
+    assert_ne!(next_keys.derived_address(), default_keys.derived_address());
+    assert_eq!(
+        next_keys.child_0_owner.get_address(),
+        wallet.get_key_factory().private_child(ChildNumber::new(KEYS_PER_ACCOUNT)).unwrap().get_address()
+    );
+}
+
+#[test]
+fn derivations_follow_index() {
+    let mut wallet = WalletLibrary::new();
+    wallet.new_address().unwrap();
+
+    let keys = KeyScheme::new_with_index(&wallet, 2);
+    let derivations = keys.derivations();
+    assert_eq!(derivations.len() as u64, KEYS_PER_ACCOUNT);
+    assert_eq!(derivations[0].child_number, 2 * KEYS_PER_ACCOUNT);
+    assert_eq!(derivations[0].public_key, keys.child_0_owner.get_public());
+    assert_eq!(derivations[4].role, "consensus");
+    assert_eq!(derivations[4].public_key, keys.child_4_consensus.get_public());
+}
+
+#[test]
+fn address_from_public_key() {
+    let mut wallet = WalletLibrary::new();
+    wallet.new_address().unwrap();
+
+    let keys = KeyScheme::new(&wallet);
+    let auth_key = auth_key_from_public(&keys.child_0_owner.get_public());
+    assert_eq!(auth_key, keys.derived_auth_key());
+    assert_eq!(auth_key.derived_address(), keys.derived_address());
 }
@@ -1,5 +1,6 @@
 //! Key generation
-use std::env;
+use std::{env, fs, path::PathBuf, process::exit};
+use zeroize::Zeroize;
 
 use diem_wallet::{Mnemonic, WalletLibrary};
 use diem_types::{
@@ -28,8 +29,12 @@ pub fn keygen() -> (AuthenticationKey, AccountAddress, WalletLibrary, String) {
         println!("0L mnemonic:\n\
         ...........................");
       
-        //use same styles as abscissa_info
-        println!("\x1b[1;36m{}\n\x1b[0m", &mnemonic_string.as_str());
+        //use same styles as abscissa_info, unless colors are off, see no-color.org
+        if std::env::var("NO_COLOR").map(|v| v.is_empty()).unwrap_or(true) {
+            println!("\x1b[1;36m{}\n\x1b[0m", &mnemonic_string.as_str());
+        } else {
+            println!("{}\n", &mnemonic_string.as_str());
+        }
 
         println!("WRITE THIS DOWN NOW. This is the last time you will see \
                   this mnemonic. It is not saved anywhere. Nobody can help \
@@ -38,15 +43,63 @@ pub fn keygen() -> (AuthenticationKey, AccountAddress, WalletLibrary, String) {
         (auth_key, account, wallet, mnemonic_string)
 }
 
+/// Normalize a mnemonic as typed or pasted by the user: trims, collapses
+/// internal whitespace and lowercases every word.
+pub fn normalize_mnemonic(mnemonic_string: &str) -> String {
+  mnemonic_string
+    .split_whitespace()
+    .map(|w| w.to_lowercase())
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
 /// Get authkey and account from mnemonic
 pub fn get_account_from_mnem(mnemonic_string: String) 
   -> (AuthenticationKey, AccountAddress, WalletLibrary) {
-      let mut wallet = WalletLibrary::new_from_mnemonic(
-        Mnemonic::from(&mnemonic_string).unwrap()
-      );
-      let (auth_key, _) = wallet.new_address().expect("Could not generate address");
+      get_account_from_mnem_with_passphrase(mnemonic_string, "")
+}
+
+/// Get authkey and account from mnemonic and BIP39 passphrase
+pub fn get_account_from_mnem_with_passphrase(mnemonic_string: String, passphrase: &str) 
+  -> (AuthenticationKey, AccountAddress, WalletLibrary) {
+      try_account_from_mnem_with_passphrase(mnemonic_string, passphrase)
+        .expect("could not derive the account from the mnemonic")
+}
+
+/// Like get_account_from_mnem_with_passphrase, returning why the derivation failed
+pub fn try_account_from_mnem_with_passphrase(mut mnemonic_string: String, passphrase: &str)
+  -> Result<(AuthenticationKey, AccountAddress, WalletLibrary), anyhow::Error> {
+      let mut normalized = normalize_mnemonic(&mnemonic_string);
+      // the words are only needed until the wallet has them
+      mnemonic_string.zeroize();
+      let mnemonic = Mnemonic::from(&normalized);
+      normalized.zeroize();
+      let mnemonic = mnemonic.map_err(|e| {
+        anyhow::anyhow!("mnemonic is not valid, check the words against the wordlist: {}", e)
+      })?;
+      let mut wallet = WalletLibrary::new_from_mnemonic_with_passphrase(mnemonic, passphrase);
+      let (auth_key, _) = wallet
+        .new_address()
+        .map_err(|e| anyhow::anyhow!("could not generate address: {}", e))?;
       let account = auth_key.derived_address();
-      (auth_key, account, wallet)
+      Ok((auth_key, account, wallet))
+}
+
+/// Times the mnemonic is prompted for before giving up
+pub const MNEMONIC_ATTEMPTS: u32 = 3;
+
+/// Prompts with read until the mnemonic and passphrase it returns derive an account,
+/// up to MNEMONIC_ATTEMPTS times, then errors.
+fn prompt_until_derived(read: impl Fn() -> (String, String))
+  -> Result<(AuthenticationKey, AccountAddress, WalletLibrary), anyhow::Error> {
+    for attempt in 1..=MNEMONIC_ATTEMPTS {
+      let (mnemonic_string, passphrase) = read();
+      match try_account_from_mnem_with_passphrase(mnemonic_string, &passphrase) {
+        Ok(account) => return Ok(account),
+        Err(e) => println!("Attempt {} of {} failed: {}", attempt, MNEMONIC_ATTEMPTS, e),
+      }
+    }
+    Err(anyhow::anyhow!("could not derive an account from the mnemonic"))
 }
 
 /// helper to return account tuple from wallet
@@ -57,7 +110,62 @@ pub fn get_account_from_wallet(wallet: &WalletLibrary) -> (AuthenticationKey, Ac
 /// Prompts user to type mnemonic securely.
 pub fn get_account_from_prompt() 
   -> (AuthenticationKey, AccountAddress, WalletLibrary) {
+    try_account_from_prompt().unwrap_or_else(|e| exit_with(e))
+}
+
+/// Prompts user to type mnemonic and then the BIP39 passphrase securely.
+pub fn get_account_from_prompt_with_passphrase() 
+  -> (AuthenticationKey, AccountAddress, WalletLibrary) {
+    try_account_from_prompt_with_passphrase().unwrap_or_else(|e| exit_with(e))
+}
+
+/// Like get_account_from_prompt, with an error instead of exiting
+pub fn try_account_from_prompt()
+  -> Result<(AuthenticationKey, AccountAddress, WalletLibrary), anyhow::Error> {
+    prompt_until_derived(|| (read_mnemonic_from_prompt(), "".to_owned()))
+}
+
+/// Like get_account_from_prompt_with_passphrase, with an error instead of exiting
+pub fn try_account_from_prompt_with_passphrase()
+  -> Result<(AuthenticationKey, AccountAddress, WalletLibrary), anyhow::Error> {
+    prompt_until_derived(|| {
+      let mnemonic_string = read_mnemonic_from_prompt();
+      (mnemonic_string, read_passphrase_from_prompt())
+    })
+}
+
+fn exit_with(e: anyhow::Error) -> ! {
+    println!("ERROR: {}, exiting.", e);
+    exit(1);
+}
+
+/// Prompts user to type the BIP39 passphrase of the mnemonic securely.
+pub fn read_passphrase_from_prompt() -> String {
+    println!("Enter your mnemonic passphrase:");
+    rpassword::read_password_from_tty(Some("\u{1F511} ")).unwrap()
+}
+
+/// Path to read a mnemonic source from: a file descriptor number is read from /dev/fd,
+/// anything else is a path, e.g. a named pipe.
+pub fn mnemonic_source_path(source: &str) -> PathBuf {
+    match source.parse::<u32>() {
+      Ok(fd) => PathBuf::from(format!("/dev/fd/{}", fd)),
+      Err(_) => PathBuf::from(source),
+    }
+}
 
+/// Reads the mnemonic from a file descriptor or named pipe, so it isn't written to disk,
+/// and derives the account. The buffer read is zeroed once the wallet is derived.
+pub fn get_account_from_fd(source: &str, passphrase: &str)
+  -> Result<(AuthenticationKey, AccountAddress, WalletLibrary), anyhow::Error> {
+    let path = mnemonic_source_path(source);
+    let buffer = fs::read_to_string(&path)
+      .map_err(|e| anyhow::anyhow!("could not read the mnemonic from {:?}: {}", &path, e))?;
+    try_account_from_mnem_with_passphrase(buffer, passphrase)
+}
+
+/// Reads the mnemonic from the tty, or from $MNEM when debugging
+fn read_mnemonic_from_prompt() -> String {
     println!("Enter your 0L mnemonic:");
 
     match env::var("NODE_ENV") {
@@ -67,14 +175,22 @@ pub fn get_account_from_prompt()
         // if we are in debugging or CI mode
         if val != "prod" && maybe_env_mnem.is_ok() {
           println!("Debugging mode, using mnemonic from env variable, $MNEM");
-          return get_account_from_mnem(maybe_env_mnem.unwrap().trim().to_string())
+          return maybe_env_mnem.unwrap()
         }
       },
       _ => {}, // default to "prod" if not set
     };
     
-    let read = rpassword::read_password_from_tty(Some("\u{1F511} "));
-    get_account_from_mnem(read.unwrap().trim().to_string())
+    rpassword::read_password_from_tty(Some("\u{1F511} ")).unwrap()
+}
+
+/// Asks the user to type back a mnemonic, to check it was written down correctly.
+pub fn confirm_mnemonic_from_prompt(mnemonic_string: &str) -> bool {
+    println!("Enter the mnemonic again to confirm you recorded it:");
+    match rpassword::read_password_from_tty(Some("\u{1F511} ")) {
+      Ok(read) => normalize_mnemonic(&read) == normalize_mnemonic(mnemonic_string),
+      Err(_) => false,
+    }
 }
 
 #[test]
@@ -104,4 +220,33 @@ fn wallet() {
 
     // Expect this to be zero before we haven't populated the address map in the repo
     assert!(vec_addresses.len() == 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn normalize_pasted_mnemonic() {
+    let eve = "recall october regret kite undo choice outside season business wall quit arrest vacant arrow giggle vote ghost winter hawk soft cheap decide exhaust spare";
+    let pasted = format!("  {}\n", eve.to_uppercase().replacen(" ", "   ", 3));
+    assert_eq!(normalize_mnemonic(&pasted), eve);
+
+    let (_, account, _) = get_account_from_mnem(pasted);
+    let (_, expected, _) = get_account_from_mnem(eve.to_string());
+    assert_eq!(account, expected);
+}
+
+#[test]
+fn passphrase_derives_another_account() {
+    let eve = "recall october regret kite undo choice outside season business wall quit arrest vacant arrow giggle vote ghost winter hawk soft cheap decide exhaust spare";
+    let (_, account, _) = get_account_from_mnem(eve.to_string());
+    let (_, empty, _) = get_account_from_mnem_with_passphrase(eve.to_string(), "");
+    let (_, with_passphrase, _) = get_account_from_mnem_with_passphrase(eve.to_string(), "hunter2");
+    assert_eq!(account, empty);
+    assert_ne!(account, with_passphrase);
+}
+
+#[test]
+fn invalid_mnemonic_is_an_error() {
+    let err = try_account_from_mnem_with_passphrase("not a mnemonic".to_owned(), "")
+      .err()
+      .expect("derivation should fail");
+    assert!(err.to_string().contains("mnemonic is not valid"));
+}
@@ -5,8 +5,8 @@ use crate::{
     delay::*,
     backlog,
 };
-use anyhow::{Error, bail};
-use byteorder::{LittleEndian, WriteBytesExt};
+use anyhow::{Context, Error, bail};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use glob::glob;
 use hex::decode;
 use diem_crypto::hash::HashValue;
@@ -19,12 +19,19 @@ use std::{
     time::Instant,
 };
 
-/// writes a JSON file with the vdf proof, ordered by a blockheight
-pub fn mine_genesis(config: &AppCfg) -> Block {
+/// Bytes of the auth key, at the start of the genesis preimage
+const AUTH_KEY_BYTES: usize = 32;
+/// Bytes of the padded chain id, after the auth key in the genesis preimage
+const CHAIN_ID_BYTES: usize = 64;
+
+/// writes a JSON file with the vdf proof, ordered by a blockheight. Returns the block
+/// and the difficulty it was mined with.
+pub fn mine_genesis(config: &AppCfg) -> Result<(Block, u64), Error> {
     println!("Mining Genesis Proof");
-    let preimage = genesis_preimage(&config);
+    let difficulty = delay_difficulty();
+    let preimage = checked_genesis_preimage(&config, difficulty)?;
     let now = Instant::now();
-    let proof = do_delay(&preimage);
+    let proof = try_delay(&preimage, difficulty)?;
     let elapsed_secs = now.elapsed().as_secs();
     println!("Delay: {:?} seconds", elapsed_secs);
     let block = Block {
@@ -34,19 +41,20 @@ pub fn mine_genesis(config: &AppCfg) -> Block {
         proof,
     };
 
-    block
+    Ok((block, difficulty))
 }
 
-/// Mines genesis and writes the file
-pub fn write_genesis(config: &AppCfg) -> Block {
-    let block = mine_genesis(config);
+/// Mines genesis and writes the file. Returns the block and the difficulty it was mined with.
+pub fn write_genesis(config: &AppCfg) -> Result<(Block, u64), Error> {
+    let (block, difficulty) = mine_genesis(config)?;
     //TODO: check for overwriting file...
-    write_json(&block, &config.get_block_dir());
+    write_json(&block, &config.get_block_dir())?;
+    write_vdf_json(&block, &config.get_block_dir(), difficulty)?;
     println!(
         "block zero proof mined, file saved to: {:?}",
         &config.get_block_dir().join("block_0.json")
     );
-    block
+    Ok((block, difficulty))
 }
 /// Mine one block
 pub fn mine_once(config: &AppCfg) -> Result<Block, Error> {
@@ -77,7 +85,7 @@ pub fn mine_once(config: &AppCfg) -> Result<Block, Error> {
             proof: data.clone(),
         };
 
-        write_json(&block, &config.get_block_dir());
+        write_json(&block, &config.get_block_dir())?;
         Ok(block)
     // Err(ErrorKind::Io.context(format!("submit_vdf_proof_tx_to_network {:?}", block_dir)).into())
     } else {
@@ -125,19 +133,55 @@ pub fn mine_and_submit(
     }
 }
 
-fn write_json(block: &Block, blocks_dir: &PathBuf) {
+fn write_json(block: &Block, blocks_dir: &PathBuf) -> Result<(), Error> {
     if !&blocks_dir.exists() {
         // first run, create the directory if there is none, or if the user changed the configs.
         // note: user may have blocks but they are in a different directory than what miner.toml says.
-        fs::create_dir(&blocks_dir).unwrap();
+        fs::create_dir(&blocks_dir)
+            .with_context(|| format!("could not create the blocks dir {:?}", blocks_dir))?;
     };
     // Write the file.
     let mut latest_block_path = blocks_dir.clone();
     latest_block_path.push(format!("block_{}.json", block.height));
     //println!("{:?}", &latest_block_path);
-    let mut file = fs::File::create(&latest_block_path).unwrap();
-    file.write_all(serde_json::to_string(&block).unwrap().as_bytes())
-        .expect("Could not write block");
+    let mut file = fs::File::create(&latest_block_path)
+        .with_context(|| format!("could not create {:?}", &latest_block_path))?;
+    file.write_all(serde_json::to_string(&block)?.as_bytes())
+        .with_context(|| format!("could not write block {:?}", &latest_block_path))
+}
+
+/// writes the VDF fields of the block separately, e.g. vdf_0.json. The name must not
+/// match block_*.json, since those are all parsed as blocks.
+fn write_vdf_json(block: &Block, blocks_dir: &PathBuf, difficulty: u64) -> Result<(), Error> {
+    let vdf_path = blocks_dir.join(format!("vdf_{}.json", block.height));
+    let vdf = VdfProof::new(&block.preimage, &block.proof, difficulty);
+    fs::write(&vdf_path, serde_json::to_string_pretty(&vdf)?)
+        .with_context(|| format!("could not write the vdf fields of the block to {:?}", &vdf_path))
+}
+
+/// The difficulty a genesis preimage claims, which follows the auth key and chain id
+pub fn preimage_difficulty(preimage: &[u8]) -> Option<u64> {
+    let offset = AUTH_KEY_BYTES + CHAIN_ID_BYTES;
+    let mut bytes = preimage.get(offset..offset + 8)?;
+    bytes.read_u64::<LittleEndian>().ok()
+}
+
+/// Checks the preimage of block zero claims the difficulty it was mined with, as returned by
+/// write_genesis, and so does the vdf_0.json next to it if that was written.
+pub fn check_genesis_difficulty(block: &Block, blocks_dir: &PathBuf, mined: u64) -> Result<(), Error> {
+    match preimage_difficulty(&block.preimage) {
+        Some(d) if d == mined => {}
+        Some(d) => bail!("block zero claims the difficulty {} in its preimage, but was mined with {}", d, mined),
+        None => bail!("the preimage of block zero is too short to have a difficulty"),
+    }
+    let vdf_path = blocks_dir.join("vdf_0.json");
+    if vdf_path.exists() {
+        let vdf: VdfProof = serde_json::from_str(&fs::read_to_string(&vdf_path)?)?;
+        if vdf.difficulty != mined {
+            bail!("{:?} records the difficulty {}, but block zero was mined with {}", vdf_path, vdf.difficulty, mined);
+        }
+    }
+    Ok(())
 }
 
 /// parse the existing blocks in the miner's path. This function receives any path. Note: the path is configured in miner.toml which abscissa Configurable parses, see commands.rs.
@@ -210,7 +254,7 @@ fn create_fixtures() {
         configs_fixture.workspace.block_dir = save_to.clone();
 
         // mine to save_to path
-        write_genesis(&configs_fixture);
+        write_genesis(&configs_fixture).unwrap();
 
         // also create mnemonic
         let mut mnemonic_path = PathBuf::from(save_to.clone());
@@ -271,7 +315,7 @@ fn test_mine_once() {
         proof: fixture_previous_proof,
     };
 
-    write_json(&fixture_block, &configs_fixture.get_block_dir());
+    write_json(&fixture_block, &configs_fixture.get_block_dir()).unwrap();
     mine_once(&configs_fixture).unwrap();
     // confirm this file was written to disk.
     let block_file = fs::read_to_string("./test_blocks_temp_2/block_1.json")
@@ -302,7 +346,7 @@ fn test_mine_genesis() {
     test_helper_clear_block_dir(&configs_fixture.get_block_dir());
 
     // mine
-    write_genesis(&configs_fixture);
+    write_genesis(&configs_fixture).unwrap();
     // read file
     let block_file =
         // TODO: make this work: let latest_block_path = &configs_fixture.chain_info.block_dir.to_string().push(format!("block_0.json"));
@@ -318,9 +362,36 @@ fn test_mine_genesis() {
     let correct_proof = "004812351796d94cab9a9932ec179630d86ced7db3fd7dbd66f36b0a9c0f1398756465304908e05c6397bffc7b6ac9b158c166eaade6169ffe54ddcd9251c149c5f3cceeacc59a9044bc9b9427237ac63d189873736b8ff2970f236f541bef4cf1d789f39f97f8b87ecccf7fd34f99bc4e193986da0761f5698f9715de76f0b5b5ffd41a4dcd73c3bbc19f1047bb2862c65699a4dc5ecbdf6297383a6a4e97d346a098f0a6d83b9aa3ccb703ab008356c45fb84a6e550f06f98c55300865f9774d0dee94bfcefc79208c35e79b3a8458ac246193ccf36f5b8d74975d1b0ecb7cefc419960fa9b31bda6582a046c70ba2aaec637a5d0a95ec59cda1edcf8a51b97463000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001";
     assert_eq!(hex::encode(&latest_block.proof), correct_proof, "test");
 
+    // the discrete vdf fields make up the same proof
+    let vdf_file = fs::read_to_string("./test_blocks_temp_1/vdf_0.json")
+        .expect("Could not read vdf fields");
+    let vdf: VdfProof = serde_json::from_str(&vdf_file).expect("could not deserialize vdf fields");
+    assert_eq!(vdf.challenge, latest_block.preimage);
+    assert_eq!([vdf.output, vdf.proof].concat(), latest_block.proof);
+
     test_helper_clear_block_dir(&configs_fixture.get_block_dir());
 }
 
+#[test]
+fn test_preimage_difficulty() {
+    let preimage = genesis_preimage(&test_make_configs_fixture());
+    assert_eq!(preimage_difficulty(&preimage), Some(delay_difficulty()));
+    assert_eq!(preimage_difficulty(&preimage[..AUTH_KEY_BYTES]), None);
+}
+
+#[test]
+fn test_check_genesis_difficulty() {
+    let block = Block {
+        height: 0u64,
+        elapsed_secs: 0u64,
+        preimage: genesis_preimage(&test_make_configs_fixture()),
+        proof: vec![],
+    };
+    let no_vdf_dir = PathBuf::from("./test_blocks_none");
+    assert!(check_genesis_difficulty(&block, &no_vdf_dir, delay_difficulty()).is_ok());
+    assert!(check_genesis_difficulty(&block, &no_vdf_dir, delay_difficulty() + 1).is_err());
+}
+
 #[test]
 fn test_parse_no_files() {
     // if no file is found, the block height is 0
@@ -370,17 +441,21 @@ pub fn test_make_configs_fixture() -> AppCfg {
 
 /// Format the config file data into a fixed byte structure for easy parsing in Move/other languages
 pub fn genesis_preimage(cfg: &AppCfg) -> Vec<u8> {
-    const AUTH_KEY_BYTES: usize = 32;
-    const CHAIN_ID_BYTES: usize = 64;
+    checked_genesis_preimage(cfg, delay_difficulty()).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// The genesis preimage claiming the difficulty, or an error if the configs don't fit its
+/// fixed byte structure
+pub fn checked_genesis_preimage(cfg: &AppCfg, difficulty: u64) -> Result<Vec<u8>, Error> {
     const STATEMENT_BYTES: usize = 1008;
 
     let mut preimage: Vec<u8> = vec![];
 
     let mut padded_key_bytes = match decode(cfg.profile.auth_key.clone()) {
-        Err(x) => panic!("Invalid 0L Auth Key: {}", x),
+        Err(x) => bail!("Invalid 0L Auth Key: {}", x),
         Ok(key_bytes) => {
             if key_bytes.len() != AUTH_KEY_BYTES {
-                panic!(
+                bail!(
                     "Expected a {} byte 0L Auth Key. Got {} bytes",
                     AUTH_KEY_BYTES,
                     key_bytes.len()
@@ -396,7 +471,7 @@ pub fn genesis_preimage(cfg: &AppCfg) -> Vec<u8> {
         let mut chain_id_bytes = cfg.chain_info.chain_id.clone().into_bytes();
 
         match chain_id_bytes.len() {
-            d if d > CHAIN_ID_BYTES => panic!(
+            d if d > CHAIN_ID_BYTES => bail!(
                 "Chain Id is longer than {} bytes. Got {} bytes",
                 CHAIN_ID_BYTES,
                 chain_id_bytes.len()
@@ -415,14 +490,13 @@ pub fn genesis_preimage(cfg: &AppCfg) -> Vec<u8> {
     preimage.append(&mut padded_chain_id_bytes);
 
     preimage
-        .write_u64::<LittleEndian>(delay_difficulty())
-        .unwrap();
+        .write_u64::<LittleEndian>(difficulty)?;
 
     let mut padded_statements_bytes = {
         let mut statement_bytes = cfg.profile.statement.clone().into_bytes();
 
         match statement_bytes.len() {
-            d if d > STATEMENT_BYTES => panic!(
+            d if d > STATEMENT_BYTES => bail!(
                 "Chain Id is longer than 1008 bytes. Got {} bytes",
                 statement_bytes.len()
             ),
@@ -450,5 +524,5 @@ pub fn genesis_preimage(cfg: &AppCfg) -> Vec<u8> {
         ),
         "Preimage is the incorrect byte length"
     );
-    return preimage;
+    Ok(preimage)
 }
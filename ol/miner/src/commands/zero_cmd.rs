@@ -4,6 +4,7 @@ use crate::{application::app_config, block::write_genesis};
 /// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
 /// accessors along with logging macros. Customize as you see fit.
 use abscissa_core::{Command, Options, Runnable};
+use std::process::exit;
 
 
 #[derive(Command, Debug, Options)]
@@ -14,6 +15,9 @@ impl Runnable for ZeroCmd {
     fn run(&self) {
         // Assumes the app has already been initialized.
         let miner_config = app_config().clone();
-        write_genesis(&miner_config);
+        if let Err(err) = write_genesis(&miner_config) {
+            println!("ERROR: could not mine the genesis proof, message: {:?}", err);
+            exit(1);
+        }
     }
 }
\ No newline at end of file
@@ -5,6 +5,8 @@
 use vdf::{VDFParams, WesolowskiVDFParams, VDF};
 use std::env;
 use diem_global_constants::VDF_SECURITY_PARAM;
+use serde::{Deserialize, Serialize};
+use anyhow::Error;
 
 /// Switch settings between production and testing
 pub fn delay_difficulty() -> u64 {
@@ -21,11 +23,15 @@ pub fn delay_difficulty() -> u64 {
 
 /// Runs the VDF
 pub fn do_delay(preimage: &[u8]) -> Vec<u8> {
-    let delay_length = delay_difficulty();
+    try_delay(preimage, delay_difficulty()).expect("iterations should have been valiated earlier")
+}
+
+/// Runs the VDF for delay_length iterations, with an error for the iterations it rejects
+pub fn try_delay(preimage: &[u8], delay_length: u64) -> Result<Vec<u8>, Error> {
     // Functions for running the VDF.
     let vdf: vdf::WesolowskiVDF = WesolowskiVDFParams(VDF_SECURITY_PARAM).new();
     vdf.solve(preimage, delay_length)
-        .expect("iterations should have been valiated earlier")
+        .map_err(|e| Error::msg(format!("the VDF rejected {} iterations: {:?}", delay_length, e)))
 }
 
 /// Verifies a proof
@@ -39,4 +45,40 @@ pub fn verify(preimage: &[u8], proof: &[u8]) -> bool{
         false
        }
     }
-}
\ No newline at end of file
+}
+/// The discrete VDF fields of a proof, for tools verifying it against the
+/// public VDF parameters without the block structure.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VdfProof {
+    /// VDF construction
+    pub algorithm: String,
+    /// security parameter, the bit length of the discriminant
+    pub security_param: u16,
+    /// number of iterations
+    pub difficulty: u64,
+    /// VDF input
+    #[serde(with = "hex")]
+    pub challenge: Vec<u8>,
+    /// VDF output
+    #[serde(with = "hex")]
+    pub output: Vec<u8>,
+    /// Wesolowski proof of the output
+    #[serde(with = "hex")]
+    pub proof: Vec<u8>,
+}
+
+impl VdfProof {
+    /// Splits a solution, which is the VDF output followed by the proof of equal length,
+    /// of difficulty iterations
+    pub fn new(preimage: &[u8], solution: &[u8], difficulty: u64) -> Self {
+        let (output, proof) = solution.split_at(solution.len() / 2);
+        VdfProof {
+            algorithm: "wesolowski".to_owned(),
+            security_param: VDF_SECURITY_PARAM,
+            difficulty,
+            challenge: preimage.to_vec(),
+            output: output.to_vec(),
+            proof: proof.to_vec(),
+        }
+    }
+}
@@ -1,9 +1,9 @@
 //! MinerApp Abscissa Application
 
-use crate::{commands::WizCmd, entrypoint::EntryPoint};
+use crate::{commands::WizCmd, entrypoint::EntryPoint, status};
 use abscissa_core::{
     application::{self, AppCell},
-    config, trace, Application, FrameworkError, StandardPaths,
+    config, terminal::ColorChoice, trace, Application, FrameworkError, StandardPaths,
 };
 use ol_types::config::AppCfg;
 /// Application state
@@ -98,6 +98,17 @@ impl Application for OnboardApp {
         Ok(())
     }
 
+    /// No colors with --no-color or NO_COLOR, otherwise colors when the terminal has them
+    fn term_colors(&self, command: &EntryPoint<WizCmd>) -> ColorChoice {
+        if status::colors_enabled(command.no_color) {
+            ColorChoice::Auto
+        } else {
+            // output of the other crates, e.g. the mnemonic of keygen, checks the variable too
+            std::env::set_var(status::NO_COLOR_ENV, "1");
+            ColorChoice::Never
+        }
+    }
+
     /// Get tracing configuration from command-line options
     fn tracing_config(&self, command: &EntryPoint<WizCmd>) -> trace::Config {
         if command.verbose {
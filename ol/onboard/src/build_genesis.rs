@@ -0,0 +1,81 @@
+//! Build genesis locally from the contributions of the genesis validators, as at a ceremony
+
+use anyhow::{bail, Context, Error};
+use diem_genesis_tool::storage_helper::StorageHelper;
+use diem_types::{chain_id::ChainId, waypoint::Waypoint};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Layout file in the contributions directory, listing the owners and operators in genesis
+pub const LAYOUT_FILE: &str = "layout.toml";
+
+/// File in the node home where the contributions are merged, as a disk backend for the genesis tool
+pub const CONTRIBUTIONS_FILE: &str = "genesis_contributions.json";
+
+/// Merges every *.json of the directory, each the disk storage export of one validator's
+/// namespaces, into one disk storage. The same entry with different values in two files is an error.
+pub fn merge_contributions(dir: &Path, out: &Path) -> Result<usize, Error> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("could not read contributions directory {:?}", dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        bail!("no contributions (*.json) found in {:?}", dir);
+    }
+
+    let mut merged = Map::new();
+    for path in &files {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("could not read contribution {:?}", path))?;
+        let entries: Map<String, Value> = serde_json::from_str(&text)
+            .with_context(|| format!("contribution {:?} is not a disk storage json", path))?;
+        for (key, value) in entries {
+            match merged.get(&key) {
+                Some(existing) if existing != &value => {
+                    bail!("contribution {:?} conflicts with an earlier one on {}", path, key)
+                }
+                _ => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    fs::write(out, serde_json::to_string_pretty(&merged)?)
+        .with_context(|| format!("could not write merged contributions {:?}", out))?;
+    Ok(files.len())
+}
+
+/// Builds genesis.blob in the node home from the contributions directory, and inserts its
+/// waypoint in the key store of the namespace. Returns the genesis waypoint.
+pub fn build_genesis(
+    home_path: &PathBuf,
+    contributions: &Path,
+    chain_id: u8,
+    namespace: &str,
+) -> Result<Waypoint, Error> {
+    let layout_path = contributions.join(LAYOUT_FILE);
+    if !layout_path.exists() {
+        bail!("the contributions directory needs a {}, found none in {:?}", LAYOUT_FILE, contributions);
+    }
+    let merged_path = home_path.join(CONTRIBUTIONS_FILE);
+    let count = merge_contributions(contributions, &merged_path)?;
+    println!("Building genesis from {} contributions in {:?}", count, contributions);
+
+    let remote = format!("backend=disk;path={}", merged_path.display());
+    let genesis_path = home_path.join("genesis.blob");
+    let storage_helper = StorageHelper::get_with_path(home_path.clone());
+    let waypoint = storage_helper
+        .build_genesis_with_layout(ChainId::new(chain_id), &remote, &genesis_path, &layout_path)
+        .map_err(|e| Error::msg(format!("could not build genesis: {:?}", e)))?;
+    storage_helper
+        .insert_waypoint(namespace, waypoint)
+        .map_err(|e| Error::msg(format!("could not insert the genesis waypoint: {:?}", e)))?;
+    // same layout as the genesis repos, so the genesis can be verified and shared as is
+    fs::write(home_path.join("genesis_waypoint"), waypoint.to_string())?;
+    Ok(waypoint)
+}
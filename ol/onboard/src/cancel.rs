@@ -0,0 +1,163 @@
+//! Clean up partial artifacts when the wizard is cancelled with Ctrl-C
+
+use anyhow::{bail, Context, Error};
+use once_cell::sync::Lazy;
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Exit code of a wizard run cancelled by the operator
+pub const CANCELLED_EXIT_CODE: i32 = 130;
+
+/// Lockfile written to the node home while the wizard is running
+pub const LOCK_FILE: &str = "onboard.lock";
+
+/// Files which are being written, and are incomplete if the wizard stops now
+static IN_PROGRESS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Files the wizard run has written so far, which are partial artifacts if it's cancelled
+static WRITTEN: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// The error of a phase which stopped because the token was cancelled
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wizard cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Lockfile of a wizard run in the node home. It is removed when dropped, whether the run
+/// completed or stopped on an error, and by the Ctrl-C handler.
+#[derive(Debug)]
+pub struct HomeLock(PathBuf);
+
+impl HomeLock {
+    /// Writes the lockfile. Errors if there is one already, of another run or of one which
+    /// did not finish.
+    pub fn new(home_path: &Path) -> Result<Self, Error> {
+        let lock_path = home_path.join(LOCK_FILE);
+        if lock_path.exists() {
+            bail!(
+                "found {:?}, another wizard run is using the home or a previous one did not finish. \
+                If no wizard is running, check the files of the home and remove the lockfile",
+                &lock_path
+            );
+        }
+        // create_new, so two runs started at once can't both take the lock
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .with_context(|| format!("could not write the wizard lockfile {:?}", &lock_path))?;
+        file.write_all(std::process::id().to_string().as_bytes())
+            .with_context(|| format!("could not write the wizard lockfile {:?}", &lock_path))?;
+        track(lock_path.clone());
+        Ok(HomeLock(lock_path))
+    }
+}
+
+impl Drop for HomeLock {
+    fn drop(&mut self) {
+        untrack(&self.0);
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// Installs the Ctrl-C handler of `onboard val`, once per process. The first Ctrl-C cancels
+/// the token, so the phases stop at their next check. A second one, e.g. while mining,
+/// removes the partial artifacts and the lockfile, and exits.
+pub fn install_handler(token: CancelToken) {
+    ctrlc::set_handler(move || {
+        if !token.is_cancelled() {
+            println!("\nCancelling the wizard after the current step, press Ctrl-C again to stop now.");
+            token.cancel();
+            return;
+        }
+        report_removed(remove_partial());
+        exit(CANCELLED_EXIT_CODE);
+    })
+    .expect("could not set Ctrl-C handler");
+}
+
+/// Removes the files being written and the ones written so far. Errors with the files
+/// which are still there.
+pub fn remove_partial() -> Result<(), Error> {
+    let mut paths: Vec<PathBuf> = IN_PROGRESS.lock().unwrap().drain(..).collect();
+    paths.append(&mut WRITTEN.lock().unwrap().drain(..).collect());
+    let left: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|p| p.exists() && fs::remove_file(p).is_err())
+        .collect();
+    if !left.is_empty() {
+        bail!("could not remove {:?}", left);
+    }
+    Ok(())
+}
+
+/// Prints whether the partial artifacts of a cancelled run were removed
+pub fn report_removed(removed: Result<(), Error>) {
+    match removed {
+        Ok(_) => println!("\nWizard cancelled, partial artifacts removed."),
+        Err(e) => println!("\nWizard cancelled, remove the partial artifacts by hand: {}", e),
+    }
+}
+
+/// Register a file the wizard wrote, it will be removed if the wizard is cancelled
+pub fn written(path: PathBuf) {
+    let mut files = WRITTEN.lock().unwrap();
+    if !files.contains(&path) {
+        files.push(path);
+    }
+}
+
+/// Mark a file as in progress, it will be removed if the wizard is cancelled
+pub fn track(path: PathBuf) {
+    IN_PROGRESS.lock().unwrap().push(path);
+}
+
+/// The file was completely written, keep it if the wizard is cancelled
+pub fn untrack(path: &Path) {
+    IN_PROGRESS.lock().unwrap().retain(|p| p != path);
+}
+
+/// Token shared with the caller driving the wizard phases, which stop at the next
+/// check once it is cancelled. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// a token which is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ask the phases using this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// whether cancel was called
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Err with Cancelled if cancelled, for phases to return early with `?`
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
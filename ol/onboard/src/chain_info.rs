@@ -0,0 +1,73 @@
+//! Chain info attested by a genesis authority, to trust the epoch and waypoint fetched online
+
+use anyhow::{bail, Context, Error};
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    Signature, ValidCryptoMaterialStringExt,
+};
+use diem_types::waypoint::Waypoint;
+use reqwest::Url;
+use serde::Deserialize;
+use std::fs;
+
+/// Extension of the detached signature, next to the chain_info.json
+pub const SIGNATURE_EXTENSION: &str = ".sig";
+
+/// Contents of chain_info.json, in the format of the web monitor's epoch.json
+#[derive(Debug, Deserialize)]
+pub struct ChainInfoAttestation {
+    /// epoch the node starts syncing from
+    pub epoch: u64,
+    /// waypoint the node starts syncing from
+    pub waypoint: Waypoint,
+}
+
+/// Reads a file, or fetches it if the source is an http(s) URL
+fn read_source(source: &str) -> Result<Vec<u8>, Error> {
+    match Url::parse(source) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            let res = reqwest::blocking::get(url.clone())
+                .with_context(|| format!("could not fetch {}", url))?
+                .error_for_status()?;
+            Ok(res.bytes()?.to_vec())
+        }
+        _ => fs::read(source).with_context(|| format!("could not read {}", source)),
+    }
+}
+
+/// Checks the detached signature of the chain info, a hex ed25519 signature over the file's bytes
+pub fn verify(data: &[u8], signature_hex: &str, pubkey: &Ed25519PublicKey) -> Result<(), Error> {
+    let signature = Ed25519Signature::from_encoded_string(signature_hex.trim())
+        .map_err(|e| Error::msg(format!("signature is not a hex ed25519 signature: {:?}", e)))?;
+    signature
+        .verify_arbitrary_msg(data, pubkey)
+        .context("chain info is not signed by the configured key")
+}
+
+/// Reads the chain info from the source and its signature from source + ".sig".
+/// Without a public key, or if the signature is missing or invalid, the chain info is
+/// rejected, unless insecure is set, in which case it is used with a warning.
+pub fn read_chain_info(
+    source: &str,
+    pubkey: Option<&Ed25519PublicKey>,
+    insecure: bool,
+) -> Result<ChainInfoAttestation, Error> {
+    let data = read_source(source)?;
+    let info: ChainInfoAttestation = serde_json::from_slice(&data)
+        .with_context(|| format!("{} is not a chain info json", source))?;
+
+    let checked = match pubkey {
+        Some(key) => read_source(&format!("{}{}", source, SIGNATURE_EXTENSION))
+            .and_then(|sig| verify(&data, &String::from_utf8_lossy(&sig), key)),
+        None => Err(Error::msg("no public key to verify the chain info with")),
+    };
+    match checked {
+        Ok(()) => println!("Chain info signature verified"),
+        Err(e) if insecure => println!(
+            "WARN: using chain info which could not be verified, since --insecure-chain-info is set. Message: {:?}",
+            e
+        ),
+        Err(e) => bail!("chain info rejected, pass --insecure-chain-info to use it anyway: {:?}", e),
+    }
+    Ok(info)
+}
@@ -7,13 +7,43 @@ pub mod wizard_val_cmd;
 pub mod wizard_fork_cmd;
 pub mod keygen_cmd;
 pub mod fix_cmd;
+pub mod templates_cmd;
+pub mod validate_cmd;
+pub mod diff_manifest_cmd;
+pub mod peer_id_cmd;
+pub mod set_key_cmd;
+pub mod account_info_cmd;
+pub mod dump_config_env_cmd;
+pub mod restore_keystore_cmd;
+pub mod create_validator_cmd;
+pub mod verify_keystore_cmd;
+pub mod registration_bundle_cmd;
+pub mod resign_autopay_cmd;
+pub mod listen_addresses_cmd;
+pub mod address_from_key_cmd;
+pub mod migrate_manifest_cmd;
 
 use self::{
     version_cmd::VersionCmd, wizard_fn_cmd::FnWizardCmd, wizard_user_cmd::UserWizardCmd,
     wizard_val_cmd::ValWizardCmd,
     keygen_cmd::KeygenCmd,
     fix_cmd::FixCmd,
-    wizard_fork_cmd::ForkCmd
+    wizard_fork_cmd::ForkCmd,
+    templates_cmd::TemplatesCmd,
+    validate_cmd::ValidateCmd,
+    diff_manifest_cmd::DiffManifestCmd,
+    peer_id_cmd::PeerIdCmd,
+    set_key_cmd::SetKeyCmd,
+    account_info_cmd::AccountInfoCmd,
+    dump_config_env_cmd::DumpConfigEnvCmd,
+    restore_keystore_cmd::RestoreKeystoreCmd,
+    create_validator_cmd::CreateValidatorCmd,
+    verify_keystore_cmd::VerifyKeystoreCmd,
+    registration_bundle_cmd::RegistrationBundleCmd,
+    resign_autopay_cmd::ResignAutopayCmd,
+    listen_addresses_cmd::ListenAddressesCmd,
+    address_from_key_cmd::AddressFromKeyCmd,
+    migrate_manifest_cmd::MigrateManifestCmd,
 
 };
 use abscissa_core::{Command, Configurable, Help, Options, Runnable};
@@ -57,7 +87,66 @@ pub enum WizCmd {
         /// The `fork` subcommand
     #[options(help = "create configs for a fork, from genesis")]
     Fork(ForkCmd),
-    
+
+    /// The `templates` subcommand
+    #[options(help = "list and select community templates")]
+    Templates(TemplatesCmd),
+
+    /// The `validate-manifest` subcommand
+    #[options(help = "check an account.json produced elsewhere")]
+    ValidateManifest(ValidateCmd),
+
+    /// The `diff-manifest` subcommand
+    #[options(help = "compare two account.json files field by field")]
+    DiffManifest(DiffManifestCmd),
+
+    /// The `peer-id` subcommand
+    #[options(help = "print the network keys and addresses of the node, to share with peers")]
+    PeerId(PeerIdCmd),
+
+    /// The `set-key` subcommand
+    #[options(help = "replace one network key in key_store.json")]
+    SetKey(SetKeyCmd),
+
+    /// The `account-info` subcommand
+    #[options(help = "print the account, ip, and autopay of an existing account.json")]
+    AccountInfo(AccountInfoCmd),
+
+    /// The `dump-config-env` subcommand
+    #[options(help = "print shell exports of the home, account, and endpoints in 0L.toml")]
+    DumpConfigEnv(DumpConfigEnvCmd),
+
+    /// The `restore-keystore` subcommand
+    #[options(help = "write key_store.json again from the mnemonic, without the rest of the wizard")]
+    RestoreKeystore(RestoreKeystoreCmd),
+
+    /// The `create-validator` subcommand
+    #[options(help = "fetch the account.json of a new validator, check it, and create the account as its funder")]
+    CreateValidator(CreateValidatorCmd),
+
+    /// The `verify-keystore` subcommand
+    #[options(help = "check key_store.json against the checksum written with it")]
+    VerifyKeystore(VerifyKeystoreCmd),
+
+    /// The `registration-bundle` subcommand
+    #[options(help = "package account.json, block zero, and network keys in a signed zip for the genesis coordinator")]
+    RegistrationBundle(RegistrationBundleCmd),
+
+    /// The `resign-autopay` subcommand
+    #[options(help = "sign the autopay of an account.json again, from the current sequence number of the account")]
+    ResignAutopay(ResignAutopayCmd),
+
+    /// The `listen-addresses` subcommand
+    #[options(help = "print the addresses and ports the node listens on, for the firewall")]
+    ListenAddresses(ListenAddressesCmd),
+
+    /// The `address-from-key` subcommand
+    #[options(help = "print the account and auth key of an owner public key, without the mnemonic")]
+    AddressFromKey(AddressFromKeyCmd),
+
+    /// The `migrate-manifest` subcommand
+    #[options(help = "upgrade an account.json of an older onboard to the current schema")]
+    MigrateManifest(MigrateManifestCmd),
 }
 
 /// This trait allows you to define how application configuration is loaded.
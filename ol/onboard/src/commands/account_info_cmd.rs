@@ -0,0 +1,31 @@
+//! `account-info` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::{manifest, prelude::app_config};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process::exit};
+
+/// `account-info` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct AccountInfoCmd {
+    #[options(help = "node home with the account.json, defaults to the one in 0L.toml")]
+    home: Option<PathBuf>,
+}
+
+impl Runnable for AccountInfoCmd {
+    fn run(&self) {
+        let home = self
+            .home
+            .clone()
+            .unwrap_or_else(|| app_config().workspace.node_home.clone());
+        let path = home.join("account.json");
+        match manifest::read_manifest(&path) {
+            Ok(m) => manifest::print_summary(&m),
+            Err(e) => {
+                println!("ERROR: could not read manifest {:?}, message: {:?}", &path, e);
+                exit(1);
+            }
+        }
+    }
+}
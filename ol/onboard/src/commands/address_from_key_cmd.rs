@@ -0,0 +1,36 @@
+//! `address-from-key` subcommand
+
+#![allow(clippy::never_loop)]
+
+use abscissa_core::{Command, Options, Runnable};
+use diem_crypto::{ed25519::Ed25519PublicKey, ValidCryptoMaterialStringExt};
+use ol_keys::scheme::auth_key_from_public;
+use std::process::exit;
+
+/// `address-from-key` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct AddressFromKeyCmd {
+    #[options(free, help = "hex encoded ed25519 public key of the owner")]
+    public_key: Vec<String>,
+}
+
+impl Runnable for AddressFromKeyCmd {
+    fn run(&self) {
+        let hex = match self.public_key.as_slice() {
+            [key] => key.trim_start_matches("0x"),
+            _ => {
+                println!("ERROR: pass one hex public key, exiting.");
+                exit(1);
+            }
+        };
+        let public_key = Ed25519PublicKey::from_encoded_string(hex).unwrap_or_else(|e| {
+            println!("ERROR: {} is not a hex ed25519 public key, exiting. Message: {:?}", hex, e);
+            exit(1);
+        });
+
+        // the same derivation as the accounts of the wizard
+        let auth_key = auth_key_from_public(&public_key);
+        println!("account: {}", auth_key.derived_address());
+        println!("auth key: {}", auth_key);
+    }
+}
@@ -0,0 +1,127 @@
+//! `create-validator` subcommand
+
+#![allow(clippy::never_loop)]
+
+use super::wizard_val_cmd::autopay_tx_hashes;
+use crate::{home, manifest};
+use abscissa_core::{Command, Options, Runnable};
+use anyhow::{Context, Error};
+use ol_keys::wallet;
+use ol_types::config::TxType;
+use reqwest::Url;
+use std::{fs, path::PathBuf, process::exit};
+use txs::{
+    commands::create_validator_cmd::create_validator_script_function,
+    relay,
+    submit_tx::{self, SubmitPolicy},
+};
+
+/// `create-validator` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct CreateValidatorCmd {
+    #[options(help = "URL of the account.json of the new validator")]
+    account_json_url: Option<Url>,
+    #[options(help = "node home of the funding account, defaults to the one in 0L.toml")]
+    home: Option<PathBuf>,
+    #[options(help = "URL of the node to submit to, defaults to the default_node of 0L.toml")]
+    url: Option<Url>,
+    #[options(help = "times to submit again when a tx bounces, e.g. the mempool is full, defaults to 0")]
+    submit_retries: Option<u32>,
+    #[options(help = "seconds each submission waits for the tx to execute, defaults to 60")]
+    submit_timeout_secs: Option<u64>,
+    #[options(no_short, help = "confirm each autopay instruction of the manifest on the terminal")]
+    review_autopay: bool,
+}
+
+/// Downloads the manifest into the home, named after the host it came from
+pub fn download_manifest(url: &Url, home_path: &PathBuf) -> Result<PathBuf, Error> {
+    let res = reqwest::blocking::get(url.clone())
+        .with_context(|| format!("could not fetch {}", url))?
+        .error_for_status()?;
+    let host = url.host_str().unwrap_or("manifest");
+    let path = home_path.join(format!("{}.account.json", host));
+    fs::write(&path, res.bytes()?).with_context(|| format!("could not write {:?}", &path))?;
+    Ok(path)
+}
+
+impl Runnable for CreateValidatorCmd {
+    fn run(&self) {
+        let url = self.account_json_url.clone().unwrap_or_else(|| {
+            println!("ERROR: pass the manifest of the validator with --account-json-url, exiting.");
+            exit(1);
+        });
+        let cfg = home::read_configs(&self.home);
+
+        let path = download_manifest(&url, &cfg.workspace.node_home).unwrap_or_else(|e| {
+            println!("ERROR: could not download the manifest, exiting. Message: {:?}", e);
+            exit(1);
+        });
+        let checks = manifest::validate_manifest(&path).unwrap_or_else(|e| {
+            println!("ERROR: could not read manifest {:?}, exiting. Message: {:?}", &path, e);
+            exit(1);
+        });
+        if !manifest::print_report(&path, &checks) {
+            println!("ERROR: the manifest did not pass validation, not creating the account, exiting.");
+            exit(1);
+        }
+        let new_account = manifest::read_manifest(&path).unwrap();
+        if let Err(e) = new_account.check_autopay(self.review_autopay) {
+            println!("ERROR: cannot send atomic account creation transaction, exiting. Message: {:?}", e);
+            exit(1);
+        }
+
+        let node_url = match (&self.url, &cfg.profile.default_node) {
+            (Some(u), _) => u.clone(),
+            (None, Some(u)) => cfg.rpc_url(u.clone()),
+            (None, None) => {
+                println!("ERROR: no default_node in 0L.toml, pass --url, exiting.");
+                exit(1);
+            }
+        };
+        println!("Funding account, enter its mnemonic");
+        let (_, _, funder) = wallet::get_account_from_prompt();
+        let tx_params = submit_tx::get_tx_params_from_toml(
+            cfg.clone(),
+            TxType::Mgmt,
+            Some(&funder),
+            node_url,
+            None,
+            false,
+        )
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not get the tx params, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        let default_policy = SubmitPolicy::default();
+        let policy = SubmitPolicy {
+            retries: self.submit_retries.unwrap_or(default_policy.retries),
+            timeout_secs: self.submit_timeout_secs.unwrap_or(default_policy.timeout_secs),
+        };
+        println!("Sending account creation transaction for {}", &new_account.ow_human_name);
+        match submit_tx::submit_with_policy(
+            create_validator_script_function(&new_account),
+            &tx_params,
+            &policy,
+        ) {
+            Ok(tx) => println!(
+                "Validator account {} created, tx hash: {}",
+                &new_account.ow_human_name,
+                autopay_tx_hashes(&[tx])[0]
+            ),
+            Err(e) => {
+                println!("ERROR: could not create the validator account, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        }
+
+        if let Some(signed) = &new_account.autopay_signed {
+            println!("\nRelaying {} signed autopay transactions", signed.len());
+            if let Err(e) = relay::relay_batch_with_policy(signed, &tx_params, &policy) {
+                println!("ERROR: could not relay the autopay transactions, exiting. Message: {:?}", e);
+                exit(1);
+            }
+            println!("Autopay transactions relayed");
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! `diff-manifest` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::manifest;
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process::exit};
+
+/// `diff-manifest` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct DiffManifestCmd {
+    #[options(free, help = "the two account.json files to compare")]
+    manifests: Vec<PathBuf>,
+}
+
+impl Runnable for DiffManifestCmd {
+    fn run(&self) {
+        if self.manifests.len() != 2 {
+            println!("ERROR: pass the two account.json files to compare, exiting.");
+            exit(1);
+        }
+        let (path_a, path_b) = (&self.manifests[0], &self.manifests[1]);
+
+        let read = |path: &PathBuf| {
+            manifest::read_manifest(path).unwrap_or_else(|e| {
+                println!("ERROR: could not read manifest {:?}, message: {:?}", path, e);
+                exit(1);
+            })
+        };
+        let (a, b) = (read(path_a), read(path_b));
+
+        let diffs = manifest::diff_manifests(&a, &b);
+        manifest::print_diff(path_a, path_b, &diffs);
+        if !diffs.is_empty() {
+            exit(1);
+        }
+    }
+}
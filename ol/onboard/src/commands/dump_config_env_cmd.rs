@@ -0,0 +1,63 @@
+//! `dump-config-env` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::home;
+use abscissa_core::{Command, Options, Runnable};
+use ol_types::config::AppCfg;
+use std::path::PathBuf;
+
+/// `dump-config-env` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct DumpConfigEnvCmd {
+    #[options(help = "node home with the 0L.toml, defaults to $OL_NODE_HOME or ~/.0L")]
+    home: Option<PathBuf>,
+}
+
+impl Runnable for DumpConfigEnvCmd {
+    fn run(&self) {
+        let cfg = home::read_configs(&self.home);
+        config_env(&cfg)
+            .iter()
+            .for_each(|(k, v)| println!("export {}={}", k, shell_quote(v)));
+    }
+}
+
+/// Variables for the resolved configs, for scripts to `eval $(onboard dump-config-env)`
+pub fn config_env(cfg: &AppCfg) -> Vec<(&'static str, String)> {
+    let upstream: Vec<String> = cfg
+        .profile
+        .upstream_nodes
+        .as_ref()
+        .map(|nodes| nodes.iter().map(|u| u.to_string()).collect())
+        .unwrap_or_default();
+    vec![
+        (home::NODE_HOME_ENV, cfg.workspace.node_home.display().to_string()),
+        ("OL_ACCOUNT", cfg.profile.account.to_string()),
+        ("OL_AUTH_KEY", cfg.profile.auth_key.clone()),
+        ("OL_IP", cfg.profile.ip.to_string()),
+        (
+            "OL_DEFAULT_NODE",
+            cfg.profile
+                .default_node
+                .as_ref()
+                .map(|u| cfg.rpc_url(u.clone()).to_string())
+                .unwrap_or_default(),
+        ),
+        ("OL_UPSTREAM_NODES", upstream.join(",")),
+        ("OL_CHAIN_ID", cfg.chain_info.chain_id.clone()),
+        (
+            "OL_BASE_EPOCH",
+            cfg.chain_info.base_epoch.map(|e| e.to_string()).unwrap_or_default(),
+        ),
+        (
+            "OL_BASE_WAYPOINT",
+            cfg.chain_info.base_waypoint.map(|w| w.to_string()).unwrap_or_default(),
+        ),
+    ]
+}
+
+/// single quotes a value for sh, escaping the quotes inside it
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -2,12 +2,17 @@
 
 #![allow(clippy::never_loop)]
 
-use std::{fs::File, io::Write, path::{PathBuf}};
-use crate::{application::app_config};
+use std::{collections::VecDeque, fs::{self, File, OpenOptions}, path::{Path, PathBuf}, process::exit, sync::{Arc, Mutex}, thread, time::Duration};
+use crate::{application::app_config, cancel::CancelToken};
 use abscissa_core::{Command, Options, Runnable};
+use anyhow::{bail, Context, Error};
+use dialoguer::Confirm;
+use diem_crypto::HashValue;
 use diem_genesis_tool::ol_node_files;
 use diem_types::waypoint::Waypoint;
-use ol_types::config::AppCfg;
+use ol_fixtures::get_bundled_genesis;
+use ol_types::config::{AppCfg, IS_TEST};
+use reqwest::{blocking::Response, header, StatusCode, Url};
 
 /// `files` subcommand
 #[derive(Command, Debug, Default, Options)]
@@ -24,18 +29,31 @@ pub struct FilesCmd {
     fullnode_only: bool,
     #[options(help = "optional waypoint")]
     waypoint: Option<Waypoint>,    
+    #[options(help = "fetch the genesis files first from github://ORG/REPO, or the https:// URL of a directory with them")]
+    genesis_source: Option<String>,
 }
 
 impl Runnable for FilesCmd {
     /// Print version message
     fn run(&self) {
         let miner_configs = app_config().to_owned();
+        let mut prebuilt_genesis = self.prebuilt_genesis.clone();
+        if self.genesis_source.is_some() {
+            let home_dir = miner_configs.workspace.node_home.clone();
+            let fetched = genesis_source(&self.genesis_source, &self.github_org, &self.repo)
+                .and_then(|source| get_files_from(home_dir.clone(), source, &CancelToken::new()));
+            if let Err(e) = fetched {
+                println!("ERROR: could not fetch the genesis files, exiting. Message: {:?}", e);
+                exit(1);
+            }
+            prebuilt_genesis = Some(home_dir.join("genesis.blob"));
+        }
         genesis_files(
             &miner_configs.clone(),
             &self.chain_id,
             &self.github_org,
             &self.repo,
-            &self.prebuilt_genesis,
+            &prebuilt_genesis,
             &self.fullnode_only,
             self.waypoint,
         ) 
@@ -74,34 +92,418 @@ pub fn genesis_files(
 
 }
 
+/// fetch the seed peers published with genesis, None if the repo has no seed_peers.json
+pub fn get_seed_peers(
+    github_org: &Option<String>,
+    repo: &Option<String>
+) -> Result<Option<Vec<Url>>, Error> {
+    let url = format!("{}genesis/seed_peers.json", raw_repo_url(github_org, repo));
+    let res = reqwest::blocking::get(&url)?;
+    if res.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let peers: Vec<String> = res.error_for_status()?.json()?;
+    // a malformed peer of the genesis repo is reported and left out, the others still work
+    let urls: Vec<Url> = peers
+        .iter()
+        .filter_map(|p| match Url::parse(p) {
+            Ok(url) => Some(url),
+            Err(e) => {
+                println!("WARN: skipping the seed peer {}: {}", p, e);
+                None
+            }
+        })
+        .collect();
+    println!("found {} seed peers published with genesis", urls.len());
+    Ok(Some(urls))
+}
+
+/// read a curated list of peers, a json array of peer URLs
+pub fn read_peer_file(path: &PathBuf) -> Result<Vec<Url>, Error> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("could not read peer file {:?}", path))?;
+    let peers: Vec<String> = serde_json::from_str(&text)
+        .with_context(|| format!("{:?} is not a json array of peer URLs", path))?;
+    if peers.is_empty() {
+        bail!("peer file {:?} has no peers", path);
+    }
+    peers
+        .iter()
+        .map(|p| Url::parse(p).with_context(|| format!("peer {} of {:?} is not a URL", p, path)))
+        .collect()
+}
+
+/// attempts to fetch each genesis file before giving up
+const FETCH_ATTEMPTS: u8 = 3;
+
+/// extension of a genesis file while it's downloaded, renamed away once complete
+const PART_EXTENSION: &str = ".part";
+
+/// extension of the sha3 hex a source can publish next to a genesis file
+const SHA3_EXTENSION: &str = ".sha3";
+
+/// genesis files downloaded at the same time
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// files of the genesis set, as (name in the repo, name in node home)
+const GENESIS_FILES: &[(&str, &str)] = &[
+    ("genesis_waypoint.txt", "genesis_waypoint"),
+    ("genesis.blob", "genesis.blob"),
+];
+
+/// Where the genesis files are fetched from. A new kind of source, e.g. an artifact store,
+/// is an impl of this and a scheme in `genesis_source`.
+pub trait GenesisSource: Send + Sync {
+    /// the file of the genesis set, by its name in GENESIS_FILES, e.g. genesis.blob
+    fn fetch(&self, name: &str) -> Result<Vec<u8>, Error>;
+    /// where the file is fetched from, for the messages
+    fn location(&self, name: &str) -> String;
+    /// Writes the file to the part file, after the offset bytes already downloaded. Returns
+    /// the size of the whole file, if the source knows it. Without ranges, it's all written again.
+    fn fetch_into(&self, name: &str, part: &Path, _offset: u64) -> Result<Option<u64>, Error> {
+        let bytes = self.fetch(name)?;
+        fs::write(part, &bytes).with_context(|| format!("could not write {:?}", part))?;
+        Ok(Some(bytes.len() as u64))
+    }
+    /// the sha3 the source publishes for the file, None if it doesn't
+    fn sha3(&self, _name: &str) -> Result<Option<HashValue>, Error> {
+        Ok(None)
+    }
+}
+
+/// The genesis directory of a github repo, the default source
+pub struct GithubSource {
+    /// github org, defaults to OLSF
+    pub github_org: Option<String>,
+    /// repo, defaults to genesis-archive
+    pub repo: Option<String>,
+}
+
+impl GenesisSource for GithubSource {
+    fn fetch(&self, name: &str) -> Result<Vec<u8>, Error> {
+        fetch_with_retries(&self.location(name))
+    }
+
+    fn fetch_into(&self, name: &str, part: &Path, offset: u64) -> Result<Option<u64>, Error> {
+        fetch_range_into(&self.location(name), part, offset)
+    }
+
+    fn sha3(&self, name: &str) -> Result<Option<HashValue>, Error> {
+        published_sha3(&self.location(&format!("{}{}", name, SHA3_EXTENSION)))
+    }
+
+    fn location(&self, name: &str) -> String {
+        format!("{}genesis/{}", raw_repo_url(&self.github_org, &self.repo), name)
+    }
+}
+
+/// A directory served over http(s), e.g. a bucket or a mirror
+pub struct UrlSource {
+    /// the directory, with the trailing slash
+    pub base: Url,
+}
+
+impl GenesisSource for UrlSource {
+    fn fetch(&self, name: &str) -> Result<Vec<u8>, Error> {
+        fetch_with_retries(&self.location(name))
+    }
+
+    fn fetch_into(&self, name: &str, part: &Path, offset: u64) -> Result<Option<u64>, Error> {
+        fetch_range_into(&self.location(name), part, offset)
+    }
+
+    fn sha3(&self, name: &str) -> Result<Option<HashValue>, Error> {
+        published_sha3(&self.location(&format!("{}{}", name, SHA3_EXTENSION)))
+    }
+
+    fn location(&self, name: &str) -> String {
+        self.base
+            .join(name)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("{}{}", self.base, name))
+    }
+}
+
+/// The source of a --genesis-source, by its scheme: github://ORG/REPO, or an http(s) URL of
+/// the directory with the files. Without one, the github repo of --github-org and --repo.
+pub fn genesis_source(
+    source: &Option<String>,
+    github_org: &Option<String>,
+    repo: &Option<String>,
+) -> Result<Arc<dyn GenesisSource>, Error> {
+    let source = match source {
+        Some(s) => s,
+        None => {
+            return Ok(Arc::new(GithubSource {
+                github_org: github_org.clone(),
+                repo: repo.clone(),
+            }))
+        }
+    };
+    let url = Url::parse(source)
+        .with_context(|| format!("genesis source {} is not a URL", source))?;
+    match url.scheme() {
+        "github" => {
+            let org = url.host_str().filter(|h| !h.is_empty()).map(|h| h.to_owned());
+            let repo = url.path().trim_matches('/');
+            if org.is_none() || repo.is_empty() || repo.contains('/') {
+                bail!("github genesis source {} is not github://ORG/REPO", source);
+            }
+            Ok(Arc::new(GithubSource {
+                github_org: org,
+                repo: Some(repo.to_owned()),
+            }))
+        }
+        "http" | "https" => {
+            // without the trailing slash, join would replace the last segment
+            let mut base = url;
+            if !base.path().ends_with('/') {
+                base.set_path(&format!("{}/", base.path()));
+            }
+            Ok(Arc::new(UrlSource { base }))
+        }
+        other => bail!(
+            "no genesis source for the scheme {}, use github:// or https://",
+            other
+        ),
+    }
+}
+
 /// fetch files from github
 pub fn get_files(
     home_dir: PathBuf,
     github_org: &Option<String>,
     repo: &Option<String>
-) {
+) -> Result<(), Error> {
+    let source = GithubSource {
+        github_org: github_org.clone(),
+        repo: repo.clone(),
+    };
+    get_files_from(home_dir, Arc::new(source), &CancelToken::new())
+}
+
+/// fetch the genesis files from a source into the node home. Once the token is cancelled
+/// the downloads in flight finish, and no other file is started.
+pub fn get_files_from(
+    home_dir: PathBuf,
+    source: Arc<dyn GenesisSource>,
+    token: &CancelToken,
+) -> Result<(), Error> {
+    // each worker takes the next file once its download is done, so one slow file doesn't hold the others
+    let queue = Arc::new(Mutex::new(GENESIS_FILES.iter().collect::<VecDeque<_>>()));
+    let workers: Vec<_> = (0..MAX_CONCURRENT_DOWNLOADS.min(GENESIS_FILES.len()))
+        .map(|_| {
+            let queue = queue.clone();
+            let source = source.clone();
+            let home_dir = home_dir.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                let mut failed = 0;
+                loop {
+                    if token.is_cancelled() {
+                        break failed;
+                    }
+                    let next = queue.lock().unwrap().pop_front();
+                    match next {
+                        Some((remote, local)) => {
+                            if let Err(e) = download(source.as_ref(), remote, &home_dir.join(local)) {
+                                println!("ERROR: {:?}", e);
+                                failed += 1;
+                            }
+                        }
+                        None => break failed,
+                    }
+                }
+            })
+        })
+        .collect();
+    let failed: usize = workers
+        .into_iter()
+        .map(|w| {
+            w.join().unwrap_or_else(|_| {
+                println!("ERROR: download thread panicked");
+                1
+            })
+        })
+        .sum();
+
+    token.check()?;
+    if failed > 0 {
+        bail!("could not fetch {} of the genesis files", failed);
+    }
+    Ok(())
+}
+
+/// Downloads to a .part file next to the path, which an interrupted download is resumed from
+/// on the retries and the next runs. It's renamed to the path once its size and sha3 check out.
+fn download(source: &dyn GenesisSource, name: &str, path: &PathBuf) -> Result<(), Error> {
+    let url = source.location(name);
+    let part = PathBuf::from(format!("{}{}", path.display(), PART_EXTENSION));
+    let mut attempt = 1;
+    let total = loop {
+        let offset = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+        if offset > 0 {
+            println!("resuming {} from byte {}", url, offset);
+        }
+        match source.fetch_into(name, &part, offset) {
+            Ok(total) => break total,
+            Err(e) if attempt < FETCH_ATTEMPTS => {
+                println!("WARN: could not fetch {}, retrying. Message: {:?}", url, e);
+                attempt += 1;
+                thread::sleep(Duration::from_secs(2));
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "could not fetch {}, the part downloaded is kept in {:?} to resume from",
+                    url, part
+                )))
+            }
+        }
+    };
+
+    // a part which doesn't check out can't be resumed from either
+    let size = fs::metadata(&part)?.len();
+    if let Some(expected) = total {
+        if size != expected {
+            fs::remove_file(&part).ok();
+            bail!("{} downloaded {} bytes, the source has {}", url, size, expected);
+        }
+    }
+    if let Some(expected) = source.sha3(name)? {
+        let hash = HashValue::sha3_256_of(&fs::read(&part)?);
+        if hash != expected {
+            fs::remove_file(&part).ok();
+            bail!("{} has the sha3 {}, the source publishes {}", url, hash, expected);
+        }
+    }
+    fs::rename(&part, path).with_context(|| format!("could not move {:?} to {:?}", part, path))?;
+    println!("{} fetched, file saved to: {:?}", url, path);
+    Ok(())
+}
+
+/// Size of the whole file in a Content-Range, e.g. bytes 100-199/200, or bytes */200
+fn content_range_total(res: &Response) -> Option<u64> {
+    res.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Fetches the bytes after the offset over http with a range request, appending them to the
+/// part. A server without ranges sends the whole file, which replaces the part.
+fn fetch_range_into(url: &str, part: &Path, offset: u64) -> Result<Option<u64>, Error> {
+    let mut req = reqwest::blocking::Client::new().get(url);
+    if offset > 0 {
+        req = req.header(header::RANGE, format!("bytes={}-", offset));
+    }
+    let res = req.send()?;
+    if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        let total = content_range_total(&res);
+        if total == Some(offset) {
+            // the part was complete, but not renamed yet
+            return Ok(total);
+        }
+        fs::remove_file(part).ok();
+        return fetch_range_into(url, part, 0);
+    }
+    let mut res = res.error_for_status()?;
+    let (total, mut file) = if res.status() == StatusCode::PARTIAL_CONTENT {
+        (content_range_total(&res), OpenOptions::new().append(true).open(part)?)
+    } else {
+        (res.content_length(), File::create(part)?)
+    };
+    res.copy_to(&mut file)
+        .with_context(|| format!("download of {} interrupted", url))?;
+    Ok(total)
+}
+
+/// The sha3 hex published at the url, None if there is none
+fn published_sha3(url: &str) -> Result<Option<HashValue>, Error> {
+    let res = reqwest::blocking::get(url)?;
+    if res.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let text = res.error_for_status()?.text()?;
+    let hash = HashValue::from_hex(text.trim())
+        .with_context(|| format!("{} is not a sha3 hex", url))?;
+    Ok(Some(hash))
+}
+
+fn fetch_with_retries(url: &str) -> Result<Vec<u8>, Error> {
+    let mut attempt = 1;
+    loop {
+        let res = reqwest::blocking::get(url)
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.bytes());
+        match res {
+            Ok(bytes) => return Ok(bytes.to_vec()),
+            Err(e) if attempt < FETCH_ATTEMPTS => {
+                println!("WARN: could not fetch {}, retrying. Message: {:?}", url, e);
+                attempt += 1;
+                thread::sleep(Duration::from_secs(2));
+            }
+            Err(e) => return Err(Error::new(e).context(format!("could not fetch {}", url))),
+        }
+    }
+}
+
+/// write the genesis files bundled in the binary for this chain id, as a last resort if they can't be fetched.
+pub fn write_bundled_genesis(home_dir: &PathBuf, chain_id: u8) -> Result<(), Error> {
+    let (blob, waypoint) = match get_bundled_genesis(chain_id) {
+        Some(g) => g,
+        None => bail!("no genesis is bundled for chain id {}", chain_id),
+    };
+
+    println!(
+        "WARN: using the genesis bundled in this binary for chain id {}.\ngenesis.blob sha3 hash: {}\ngenesis waypoint: {}\nConfirm these match the network you are joining.",
+        chain_id,
+        HashValue::sha3_256_of(blob),
+        waypoint.trim()
+    );
+    if !*IS_TEST && !Confirm::new().with_prompt("Do these match the network?").interact()? {
+        bail!("bundled genesis rejected");
+    }
+
+    fs::write(home_dir.join("genesis_waypoint"), waypoint)?;
+    fs::write(home_dir.join("genesis.blob"), blob)?;
+    println!("bundled genesis files saved to: {:?}", home_dir);
+    Ok(())
+}
+
+/// base url for raw files of the genesis repo, defaults to OLSF/genesis-archive
+pub fn raw_repo_url(github_org: &Option<String>, repo: &Option<String>) -> String {
     let github_org = github_org.clone().unwrap_or("OLSF".to_string());
     let repo = repo.clone().unwrap_or("genesis-archive".to_string());
 
-
-    let base_url = format!(
-        "https://raw.githubusercontent.com/{github_org}/{repo}/main/genesis/", 
+    format!(
+        "https://raw.githubusercontent.com/{github_org}/{repo}/main/", 
         github_org=github_org, 
         repo=repo
+    )
+}
+
+#[test]
+fn test_genesis_source() {
+    let source = |s: &str| genesis_source(&Some(s.to_owned()), &None, &None);
+
+    assert_eq!(
+        source("github://OLSF/experimental-genesis").unwrap().location("genesis.blob"),
+        "https://raw.githubusercontent.com/OLSF/experimental-genesis/main/genesis/genesis.blob"
+    );
+    assert_eq!(
+        source("https://mirror.example.com/genesis").unwrap().location("genesis.blob"),
+        "https://mirror.example.com/genesis/genesis.blob"
     );
 
-    let w_res = reqwest::blocking::get(&format!("{}genesis_waypoint.txt", base_url));
-    let w_path = &home_dir.join("genesis_waypoint");
-    let mut w_file = File::create(&w_path).expect("couldn't create file");
-    let w_content =  w_res.unwrap().text().unwrap();
-    w_file.write_all(w_content.as_bytes()).unwrap();
-    println!("genesis waypoint fetched, file saved to: {:?}", w_path);
-
-    let g_res = reqwest::blocking::get(&format!("{}genesis.blob", base_url));
-    let g_path = &home_dir.join("genesis.blob");
-    let mut g_file = File::create(&g_path).expect("couldn't create file");
-    let g_content =  g_res.unwrap().bytes().unwrap().to_vec(); //.text().unwrap();
-    g_file.write_all(g_content.as_slice()).unwrap();
-
-    println!("genesis transactions fetched, file saved to: {:?}", g_path);
-}
\ No newline at end of file
+    // github://ORG/REPO needs both, and nothing after the repo
+    assert!(source("github://OLSF").is_err());
+    assert!(source("github://OLSF/").is_err());
+    assert!(source("github:///experimental-genesis").is_err());
+    assert!(source("github://OLSF/experimental-genesis/genesis").is_err());
+    assert!(source("ftp://mirror.example.com/genesis").is_err());
+    assert!(source("not a url").is_err());
+}
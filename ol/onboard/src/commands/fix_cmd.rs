@@ -2,14 +2,16 @@
 
 #![allow(clippy::never_loop)]
 
-use std::fs;
+use std::{fs, path::PathBuf, process::exit};
 
 use crate::{commands::wizard_val_cmd::write_account_json, prelude::app_config};
-use super::wizard_val_cmd::get_autopay_batch;
+use super::wizard_val_cmd::{get_autopay_batch, AutopayOptions};
 use abscissa_core::{Command, Options, Runnable, status_info, time::{DateTime, Utc}};
-use ol_keys::wallet;
+use ol_keys::{scheme::KeyScheme, wallet};
 use ol::config::AppCfg;
 use ol_types::pay_instruction::{InstructionType, PayInstruction, write_batch_file};
+use ol_types::{account::ValConfigs, block::Block};
+use miner::delay;
 use diem_genesis_tool::key;
 use diem_types::waypoint::Waypoint;
 
@@ -22,8 +24,14 @@ pub struct FixCmd {
   #[options(help = "migrate account json")]
   account: bool,
 
+  #[options(no_short, help = "sequence number to start signing the autopay txs of --account from, defaults to the account's sequence number on the upstream peer")]
+  autopay_seq_start: Option<u64>,
+
   #[options(help = "fix operator key")]
   operator: bool,  
+
+  #[options(help = "add a block_0.json mined separately to a manifest with a pending proof")]
+  import_block_zero: Option<PathBuf>,
 }
 
 impl Runnable for FixCmd {
@@ -42,27 +50,39 @@ impl Runnable for FixCmd {
         }
 
         if self.account {
-          migrate_account_json(&cfg);
+          migrate_account_json(&cfg, self.autopay_seq_start);
+        }
+
+        if let Some(block_path) = &self.import_block_zero {
+          import_block_zero(&cfg, block_path);
         }
     }
   }
 
-/// fixes account json
-pub fn migrate_account_json(cfg: &AppCfg) {
+/// fixes account json, signing the autopay txs from seq_start or else the sequence number on the upstream peer
+pub fn migrate_account_json(cfg: &AppCfg, seq_start: Option<u64>) {
   let (_, _, wallet) = wallet::get_account_from_prompt();
   let home_path = cfg.workspace.node_home.clone();
   println!("Reading autopay configs");
   println!("\nTHIS IS NOT SUBMITTING TXs, only formatting files.\n");
 
   let (autopay_batch, autopay_signed) = get_autopay_batch(
-        &None,
-        &None,
         &home_path,
         &cfg,
         &wallet,
         false,
-        false // TODO: Do we need swarm case for this?
-    );
+        false, // TODO: Do we need swarm case for this?
+        &AutopayOptions {
+            review: true,
+            seq_start,
+            upstream: cfg.profile.upstream_nodes.as_ref().and_then(|n| n.first().cloned()),
+            ..AutopayOptions::default()
+        },
+    )
+    .unwrap_or_else(|e| {
+        println!("ERROR: could not get the autopay instructions, exiting. Message: {:?}", e);
+        exit(1);
+    });
 
     let account_json_path = cfg.workspace.node_home.clone().join("account.json");
     if account_json_path.exists() {
@@ -80,11 +100,43 @@ pub fn migrate_account_json(cfg: &AppCfg) {
     // Write account manifest
     write_account_json(
         &None,
-        wallet,
+        KeyScheme::new(&wallet),
         Some(cfg.to_owned()),
         autopay_batch,
         autopay_signed,
-    );
+        false,
+        None,
+    )
+    .unwrap_or_else(|e| {
+        println!("ERROR: could not write account.json, exiting. Message: {:?}", e);
+        exit(1);
+    });
+}
+
+/// adds a separately mined block zero to the account.json in node home
+pub fn import_block_zero(cfg: &AppCfg, block_path: &PathBuf) {
+  let block = Block::parse_block_file(block_path.to_owned());
+  if block.height != 0 || !delay::verify(&block.preimage, &block.proof) {
+    println!("ERROR: {:?} is not a valid block zero proof, exiting.", block_path);
+    exit(1);
+  }
+
+  let home_path = cfg.workspace.node_home.clone();
+  let account_json_path = home_path.join("account.json");
+  let mut manifest = ValConfigs::get_init_data(&account_json_path).unwrap_or_else(|e| {
+    println!("ERROR: could not read {:?}, exiting. Message: {:?}", &account_json_path, e);
+    exit(1);
+  });
+
+  let blocks_dir = cfg.get_block_dir();
+  fs::create_dir_all(&blocks_dir).expect("could not create blocks directory");
+  if block_path != &blocks_dir.join("block_0.json") {
+    fs::copy(block_path, blocks_dir.join("block_0.json")).expect("could not copy block_0.json");
+  }
+
+  manifest.block_zero = block;
+  manifest.proof_pending = false;
+  manifest.create_manifest(home_path);
 }
 
 /// migrate autopay.json for archive purposes
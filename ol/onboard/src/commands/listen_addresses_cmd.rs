@@ -0,0 +1,61 @@
+//! `listen-addresses` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::home;
+use abscissa_core::{Command, Options, Runnable};
+use diem_config::config::NodeConfig;
+use diem_types::network_address::{NetworkAddress, Protocol};
+use std::{path::PathBuf, process::exit};
+
+/// `listen-addresses` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct ListenAddressesCmd {
+    #[options(help = "node home with the node config, defaults to the node home, see --home-path/OL_NODE_HOME")]
+    home: Option<PathBuf>,
+    #[options(help = "node config to read, defaults to validator.node.yaml, or fullnode.node.yaml, of the node home")]
+    config: Option<PathBuf>,
+}
+
+/// Tcp port of a network address, the one to open in the firewall
+pub fn tcp_port(address: &NetworkAddress) -> Option<u16> {
+    address.as_slice().iter().find_map(|p| match p {
+        Protocol::Tcp(port) => Some(*port),
+        _ => None,
+    })
+}
+
+impl Runnable for ListenAddressesCmd {
+    fn run(&self) {
+        let home_path = home::home_override(&self.home).unwrap_or_else(home::default_home);
+        let config_path = self.config.clone().unwrap_or_else(|| {
+            let validator = home_path.join("validator.node.yaml");
+            if validator.exists() {
+                validator
+            } else {
+                home_path.join("fullnode.node.yaml")
+            }
+        });
+        let config = NodeConfig::load(&config_path).unwrap_or_else(|e| {
+            println!("ERROR: could not read the node config {:?}, exiting. Message: {:?}", &config_path, e);
+            exit(1);
+        });
+
+        println!("Listen addresses of {:?}, open their ports to the peers and clients:", &config_path);
+        let networks = config
+            .validator_network
+            .iter()
+            .chain(config.full_node_networks.iter());
+        for network in networks {
+            let port = tcp_port(&network.listen_address)
+                .map(|p| format!("tcp {}", p))
+                .unwrap_or_else(|| "no tcp port".to_owned());
+            println!("  {} network: {} ({})", network.network_id, network.listen_address, port);
+        }
+        println!(
+            "  json-rpc: {} (tcp {}), only open it if clients outside the host query the node",
+            config.json_rpc.address,
+            config.json_rpc.address.port()
+        );
+    }
+}
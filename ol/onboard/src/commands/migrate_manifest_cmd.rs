@@ -0,0 +1,67 @@
+//! `migrate-manifest` subcommand
+
+#![allow(clippy::never_loop)]
+
+use abscissa_core::{Command, Options, Runnable};
+use ol_types::account::{ValConfigs, MANIFEST_SCHEMA_VERSION};
+use std::{fs, path::PathBuf, process::exit};
+
+/// `migrate-manifest` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct MigrateManifestCmd {
+    #[options(free, help = "the account.json to upgrade")]
+    manifest: Vec<PathBuf>,
+    #[options(help = "file to write the upgraded manifest to, defaults to replacing it, with a .bak of the old one")]
+    output: Option<PathBuf>,
+}
+
+impl Runnable for MigrateManifestCmd {
+    fn run(&self) {
+        let path = match self.manifest.as_slice() {
+            [path] => path,
+            _ => {
+                println!("ERROR: pass the one account.json to upgrade, exiting.");
+                exit(1);
+            }
+        };
+        let json: serde_json::Value = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|t| Ok(serde_json::from_str(&t)?))
+            .unwrap_or_else(|e| {
+                println!("ERROR: could not read {:?} as json, exiting. Message: {:?}", path, e);
+                exit(1);
+            });
+
+        let version = ValConfigs::manifest_version(&json);
+        // a current manifest of an older binary still gets its schema_version written
+        let stamped = json.get("schema_version").and_then(|v| v.as_u64()) == Some(MANIFEST_SCHEMA_VERSION);
+        if stamped && self.output.is_none() {
+            println!("{:?} already has the current schema, version {}", path, version);
+            return;
+        }
+        println!("{:?} has schema version {}, upgrading to version {}", path, version, MANIFEST_SCHEMA_VERSION);
+        let manifest = ValConfigs::migrate(json).unwrap_or_else(|e| {
+            println!("ERROR: could not upgrade the manifest, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        let output = match &self.output {
+            Some(o) => o.clone(),
+            None => {
+                let backup = PathBuf::from(format!("{}.bak", path.display()));
+                if let Err(e) = fs::copy(path, &backup) {
+                    println!("ERROR: could not back up the manifest, exiting. Message: {:?}", e);
+                    exit(1);
+                }
+                println!("The old manifest is kept as {:?}", &backup);
+                path.clone()
+            }
+        };
+        let json = serde_json::to_string(&manifest).expect("Manifest should export to json");
+        if let Err(e) = fs::write(&output, json) {
+            println!("ERROR: could not write {:?}, exiting. Message: {:?}", &output, e);
+            exit(1);
+        }
+        println!("Upgraded manifest written to {:?}", &output);
+    }
+}
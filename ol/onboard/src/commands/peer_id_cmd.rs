@@ -0,0 +1,55 @@
+//! `peer-id` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::{home, key_store};
+use abscissa_core::{Command, Options, Runnable};
+use diem_global_constants::{FULLNODE_NETWORK_KEY, VALIDATOR_NETWORK_KEY};
+use std::{net::Ipv4Addr, path::PathBuf, process::exit};
+
+/// `peer-id` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct PeerIdCmd {
+    #[options(help = "node home with the key_store.json, defaults to the one in 0L.toml")]
+    home: Option<PathBuf>,
+    #[options(no_short, help = "suffix of the operator namespace of the key store, defaults to -oper")]
+    namespace_suffix: Option<String>,
+    #[options(help = "public ip of the node, defaults to the one in 0L.toml")]
+    ip: Option<Ipv4Addr>,
+}
+
+impl Runnable for PeerIdCmd {
+    fn run(&self) {
+        let cfg = home::read_configs(&self.home);
+        let home = cfg.workspace.node_home.clone();
+        let ip = self.ip.unwrap_or(cfg.profile.ip).to_string();
+
+        let ks = key_store::read_key_store_with_suffix(
+            &home,
+            self.namespace_suffix.as_deref().unwrap_or(key_store::DEFAULT_NAMESPACE_SUFFIX),
+        )
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not read the key store, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        let networks = [
+            ("validator network", VALIDATOR_NETWORK_KEY, 6180u16),
+            ("fullnode network", FULLNODE_NETWORK_KEY, 6179u16),
+        ];
+        for (name, key, port) in networks.iter() {
+            match ks.peer_identity(*key, &ip, *port) {
+                Ok(id) => {
+                    println!("{}", name);
+                    println!("  public key: {}", id.public_key);
+                    println!("  peer id: {}", id.peer_id);
+                    println!("  address: {}", id.address);
+                }
+                Err(e) => {
+                    println!("ERROR: could not read the {} identity, exiting. Message: {:?}", name, e);
+                    exit(1);
+                }
+            }
+        }
+    }
+}
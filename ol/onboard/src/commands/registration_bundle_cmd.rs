@@ -0,0 +1,89 @@
+//! `registration-bundle` subcommand
+
+#![allow(clippy::never_loop)]
+
+use super::wizard_val_cmd::{confirm_passphrase_account, write_account_json};
+use crate::{home, registration};
+use abscissa_core::{Command, Options, Runnable};
+use ol_keys::{scheme::KeyScheme, wallet};
+use std::{path::PathBuf, process::exit};
+
+/// `registration-bundle` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct RegistrationBundleCmd {
+    #[options(help = "node home with the 0L.toml, account.json and block zero")]
+    home: Option<PathBuf>,
+    #[options(help = "directory to write the bundle in, defaults to the node home")]
+    output_path: Option<PathBuf>,
+    #[options(help = "derivation index of the account in the mnemonic, defaults to 0")]
+    account_index: Option<u64>,
+    #[options(help = "prompt for the BIP39 passphrase of the mnemonic")]
+    mnemonic_passphrase: bool,
+}
+
+impl Runnable for RegistrationBundleCmd {
+    fn run(&self) {
+        let cfg = home::read_configs(&self.home);
+        let home_path = cfg.workspace.node_home.clone();
+
+        let wallet = if self.mnemonic_passphrase {
+            let (_, account, wallet) = wallet::get_account_from_prompt_with_passphrase();
+            confirm_passphrase_account(account).unwrap_or_else(|e| {
+                println!("ERROR: {}, exiting.", e);
+                exit(1);
+            });
+            wallet
+        } else {
+            wallet::get_account_from_prompt().2
+        };
+        let keys = KeyScheme::new_with_index(&wallet, self.account_index.unwrap_or(0));
+        if keys.derived_address() != cfg.profile.account {
+            println!(
+                "ERROR: the mnemonic is of account {}, the node of {}, exiting.",
+                keys.derived_address(),
+                cfg.profile.account
+            );
+            exit(1);
+        }
+
+        // an account.json from the wizard is kept as is, with its autopay
+        let manifest_path = home_path.join("account.json");
+        if !manifest_path.exists() {
+            let manifest_keys = KeyScheme::new_with_index(&wallet, keys.account_index);
+            if let Err(e) = write_account_json(
+                &Some(home_path.clone()),
+                manifest_keys,
+                Some(cfg.clone()),
+                None,
+                None,
+                false,
+                None,
+            ) {
+                println!("ERROR: could not write account.json, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        }
+
+        let block_path = cfg.get_block_dir().join("block_0.json");
+        let out_dir = self.output_path.clone().unwrap_or_else(|| home_path.clone());
+        match registration::write_bundle(&manifest_path, &block_path, &keys, &out_dir) {
+            Ok(path) => {
+                // the coordinator checks the signature the same way
+                let owner = keys.child_0_owner.get_public();
+                if let Err(e) = registration::verify_bundle(&path, keys.derived_address(), &owner) {
+                    println!("ERROR: the bundle written does not verify, exiting. Message: {:?}", e);
+                    exit(1);
+                }
+                println!("Registration bundle written to {}", path.display());
+                println!(
+                    "Upload it to the ceremony coordinator with its signature, {}",
+                    registration::signature_path(&path).display()
+                );
+            }
+            Err(e) => {
+                println!("ERROR: could not write the registration bundle, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! `resign-autopay` subcommand
+
+#![allow(clippy::never_loop)]
+
+use super::wizard_val_cmd::{confirm_passphrase_account, sign_autopay_instructions};
+use crate::{entrypoint, home, manifest};
+use abscissa_core::{Command, Options, Runnable};
+use ol::node::client::make_client;
+use ol_keys::{scheme::KeyScheme, wallet};
+use ol_types::pay_instruction::PayInstruction;
+use reqwest::Url;
+use std::{fs, path::PathBuf, process::exit};
+
+/// `resign-autopay` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct ResignAutopayCmd {
+    #[options(help = "node home with the 0L.toml, defaults to the one in $HOME/.0L")]
+    home: Option<PathBuf>,
+    #[options(help = "account.json to sign the autopay of again, defaults to the one in the node home")]
+    manifest: Option<PathBuf>,
+    #[options(help = "autopay file or directory to sign, instead of the instructions in the manifest")]
+    autopay_file: Option<PathBuf>,
+    #[options(help = "peer to fetch the sequence number of the account from, defaults to the upstream of 0L.toml")]
+    upstream_peer: Option<Url>,
+    #[options(help = "sign from this sequence number, instead of the one on chain")]
+    autopay_seq_start: Option<u64>,
+    #[options(help = "derivation index of the account in the mnemonic, defaults to 0")]
+    account_index: Option<u64>,
+    #[options(help = "prompt for the BIP39 passphrase of the mnemonic")]
+    mnemonic_passphrase: bool,
+}
+
+impl Runnable for ResignAutopayCmd {
+    fn run(&self) {
+        let cfg = home::read_configs(&self.home);
+        let manifest_path = self
+            .manifest
+            .clone()
+            .unwrap_or_else(|| cfg.workspace.node_home.join("account.json"));
+        let mut val_configs = manifest::read_manifest(&manifest_path).unwrap_or_else(|e| {
+            println!("ERROR: could not read the manifest, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        let base_epoch = cfg.chain_info.base_epoch;
+        let instructions = match &self.autopay_file {
+            Some(path) if path.is_dir() => PayInstruction::parse_autopay_dir(path, base_epoch, None, None)
+                .map(|(instr, _)| instr),
+            Some(path) => PayInstruction::parse_autopay_instructions(path, base_epoch, None, None),
+            None => val_configs
+                .autopay_instructions
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("the manifest has no autopay instructions, pass --autopay-file")),
+        }
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not read the autopay instructions, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        // the stale sequence number is the problem, so don't fall back to guessing one
+        let upstream = self
+            .upstream_peer
+            .clone()
+            .or_else(|| cfg.profile.upstream_nodes.as_ref().and_then(|n| n.first().cloned()));
+        let seq_start = match (self.autopay_seq_start, &upstream, cfg.chain_info.base_waypoint) {
+            (Some(n), _, _) => n,
+            (None, Some(url), Some(wp)) => {
+                match make_client(Some(url.clone()), wp).and_then(|c| c.get_account(&cfg.profile.account)) {
+                    Ok(Some(view)) => view.sequence_number,
+                    Ok(None) => 0,
+                    Err(e) => {
+                        println!("ERROR: could not fetch the sequence number from {}, exiting. Message: {:?}", url, e);
+                        exit(1);
+                    }
+                }
+            }
+            _ => {
+                println!("ERROR: no upstream peer and waypoint to fetch the sequence number, pass --upstream-peer or --autopay-seq-start, exiting.");
+                exit(1);
+            }
+        };
+        println!("Signing {} autopay instructions from sequence number {}", instructions.len(), seq_start);
+
+        let wallet = if self.mnemonic_passphrase {
+            let (_, account, wallet) = wallet::get_account_from_prompt_with_passphrase();
+            confirm_passphrase_account(account).unwrap_or_else(|e| {
+                println!("ERROR: {}, exiting.", e);
+                exit(1);
+            });
+            wallet
+        } else {
+            wallet::get_account_from_prompt().2
+        };
+        let account_index = self.account_index.unwrap_or(0);
+        let account = KeyScheme::new_with_index(&wallet, account_index).derived_address();
+        if account.to_string() != val_configs.ow_human_name {
+            println!(
+                "ERROR: the mnemonic is of account {}, the manifest of {}, exiting.",
+                account, val_configs.ow_human_name
+            );
+            exit(1);
+        }
+
+        let signed = sign_autopay_instructions(
+            &instructions,
+            &cfg,
+            &wallet,
+            account_index,
+            entrypoint::get_args().swarm_path.is_some(),
+            Some(seq_start),
+            &upstream,
+        )
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not sign the autopay instructions, exiting. Message: {:?}", e);
+            exit(1);
+        });
+        val_configs.autopay_instructions = Some(instructions);
+        val_configs.autopay_signed = Some(signed);
+
+        let backup = PathBuf::from(format!("{}.bak", manifest_path.display()));
+        if let Err(e) = fs::copy(&manifest_path, &backup) {
+            println!("ERROR: could not back up the manifest, exiting. Message: {:?}", e);
+            exit(1);
+        }
+        let json = serde_json::to_string(&val_configs).expect("Manifest should export to json");
+        if let Err(e) = fs::write(&manifest_path, json) {
+            println!("ERROR: could not write the manifest, exiting. Message: {:?}", e);
+            exit(1);
+        }
+        println!("Autopay signed again in {:?}, the previous manifest is kept as {:?}", &manifest_path, &backup);
+    }
+}
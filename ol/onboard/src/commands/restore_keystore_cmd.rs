@@ -0,0 +1,89 @@
+//! `restore-keystore` subcommand
+
+#![allow(clippy::never_loop)]
+
+use super::{wizard_val_cmd::confirm_passphrase_account, CONFIG_FILE};
+use crate::{home, key_store::{self, KEY_STORE_FILE}};
+use abscissa_core::{Command, Options, Runnable};
+use diem_types::waypoint::Waypoint;
+use ol::commands::init_cmd;
+use ol_keys::{scheme::KeyScheme, wallet};
+use ol_types::config::AppCfg;
+use std::{fs, path::PathBuf, process::exit};
+
+/// `restore-keystore` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct RestoreKeystoreCmd {
+    #[options(help = "node home to write key_store.json in")]
+    home: Option<PathBuf>,
+    #[options(help = "derivation index of the account in the mnemonic, defaults to 0")]
+    account_index: Option<u64>,
+    #[options(help = "prompt for the BIP39 passphrase of the mnemonic")]
+    mnemonic_passphrase: bool,
+    #[options(help = "waypoint to set in the key store, defaults to the base_waypoint of 0L.toml")]
+    waypoint: Option<Waypoint>,
+    #[options(help = "replace an existing key_store.json, which is kept as key_store.json.bak")]
+    force: bool,
+}
+
+impl Runnable for RestoreKeystoreCmd {
+    fn run(&self) {
+        let home_path = home::home_override(&self.home).unwrap_or_else(home::default_home);
+        let key_store_path = home_path.join(KEY_STORE_FILE);
+        if key_store_path.exists() {
+            if !self.force {
+                println!(
+                    "ERROR: {:?} exists, pass --force to replace it, exiting.",
+                    &key_store_path
+                );
+                exit(1);
+            }
+            let backup = home_path.join(format!("{}.bak", KEY_STORE_FILE));
+            fs::copy(&key_store_path, &backup).unwrap_or_else(|e| {
+                println!("ERROR: could not back up the key store, exiting. Message: {:?}", e);
+                exit(1);
+            });
+            println!("Existing key store kept as {:?}", backup);
+        }
+
+        let wallet = if self.mnemonic_passphrase {
+            let (_, account, wallet) = wallet::get_account_from_prompt_with_passphrase();
+            confirm_passphrase_account(account).unwrap_or_else(|e| {
+                println!("ERROR: {}, exiting.", e);
+                exit(1);
+            });
+            wallet
+        } else {
+            wallet::get_account_from_prompt().2
+        };
+        let keys = KeyScheme::new_with_index(&wallet, self.account_index.unwrap_or(0));
+
+        // the 0L.toml may be lost along with the key store, only the home and account are needed
+        let mut cfg = if home_path.join(CONFIG_FILE).exists() {
+            home::read_configs(&Some(home_path.clone()))
+        } else {
+            AppCfg::default()
+        };
+        cfg.workspace.node_home = home_path.clone();
+        cfg.profile.auth_key = keys.derived_auth_key().to_string();
+        cfg.profile.account = keys.derived_address();
+        let waypoint = self.waypoint.or(cfg.chain_info.base_waypoint);
+        if waypoint.is_none() {
+            println!("WARN: no waypoint to set, the node needs one set in the key store before it starts");
+        }
+
+        let account = cfg.profile.account;
+        match init_cmd::initialize_validator_keys(keys, &cfg, waypoint, false, None) {
+            Ok(()) => {
+                if let Err(e) = key_store::write_checksum(&home_path) {
+                    println!("WARN: could not write the key store checksum. Message: {:?}", e);
+                }
+                println!("Key store of account {} written to {:?}", account, &key_store_path)
+            }
+            Err(e) => {
+                println!("ERROR: could not write the key store, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        }
+    }
+}
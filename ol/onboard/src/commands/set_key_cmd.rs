@@ -0,0 +1,88 @@
+//! `set-key` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::{key_store, prelude::app_config};
+use abscissa_core::{Command, Options, Runnable};
+use diem_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
+use dialoguer::Password;
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::exit,
+};
+
+/// `set-key` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct SetKeyCmd {
+    #[options(free, help = "name of the key to replace, e.g. fullnode_network")]
+    name: Vec<String>,
+    #[options(
+        no_short,
+        help = "file with the hex encoded private key, - for stdin, prompted for if not set"
+    )]
+    key_file: Option<PathBuf>,
+    #[options(help = "node home with the key_store.json, defaults to the one in 0L.toml")]
+    home: Option<PathBuf>,
+    #[options(no_short, help = "suffix of the operator namespace of the key store, defaults to -oper")]
+    namespace_suffix: Option<String>,
+}
+
+impl Runnable for SetKeyCmd {
+    fn run(&self) {
+        let name = match self.name.as_slice() {
+            [name] => name,
+            _ => {
+                println!(
+                    "ERROR: pass the name of one key to set, one of: {}, exiting.",
+                    key_store::SETTABLE_KEYS.join(", ")
+                );
+                exit(1);
+            }
+        };
+        let home = self
+            .home
+            .clone()
+            .unwrap_or_else(|| app_config().workspace.node_home.clone());
+        let ks = key_store::read_key_store_with_suffix(
+            &home,
+            self.namespace_suffix.as_deref().unwrap_or(key_store::DEFAULT_NAMESPACE_SUFFIX),
+        )
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not read the key store, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        // never from an argument, it would leak to the shell history and the process list
+        let encoded = match &self.key_file {
+            Some(path) if path.as_os_str() == "-" => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text).map(|_| text)
+            }
+            Some(path) => fs::read_to_string(path),
+            None => Password::new()
+                .with_prompt(&format!("Private key for {}", name))
+                .interact(),
+        }
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not read the private key, exiting. Message: {:?}", e);
+            exit(1);
+        });
+        let key = Ed25519PrivateKey::from_encoded_string(encoded.trim()).unwrap_or_else(|e| {
+            println!("ERROR: the key is not a hex encoded private key, exiting. Message: {:?}", e);
+            exit(1);
+        });
+
+        match ks.set_key(name, key) {
+            Ok(()) => println!(
+                "{} updated in {:?}. Restart the node, and update the on-chain validator config if the key is in it.",
+                name, &ks.path
+            ),
+            Err(e) => {
+                println!("ERROR: could not set the key, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! `templates` subcommand
+
+#![allow(clippy::never_loop)]
+
+use super::files_cmd;
+use abscissa_core::{Command, Options, Runnable};
+use anyhow::Error;
+use dialoguer::Select;
+use reqwest::Url;
+use serde::Deserialize;
+use std::process::exit;
+
+/// `templates` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct TemplatesCmd {
+    #[options(help = "github org of genesis repo")]
+    github_org: Option<String>,
+    #[options(help = "repo with the templates index")]
+    repo: Option<String>,
+    #[options(help = "name of the template to select, otherwise pick from a list")]
+    name: Option<String>,
+}
+
+/// An entry in the community templates index
+#[derive(Clone, Debug, Deserialize)]
+pub struct TemplateEntry {
+    /// short name of the template, used to select it
+    pub name: String,
+    /// what the template configures
+    pub description: String,
+    /// url of the node serving the template, as used in --template-url
+    pub url: String,
+}
+
+impl Runnable for TemplatesCmd {
+    fn run(&self) {
+        let templates = fetch_templates(&self.github_org, &self.repo).unwrap_or_else(|e| {
+            println!("ERROR: could not fetch templates index. Message: {:?}", e);
+            exit(1);
+        });
+
+        let selected = match &self.name {
+            Some(name) => find_template(&templates, name),
+            None => pick_template(&templates).unwrap_or_else(|e| {
+                println!("ERROR: {}, exiting.", e);
+                exit(1);
+            }),
+        };
+
+        match selected {
+            Some(t) => {
+                println!("Selected template: {}\n{}\n", t.name, t.description);
+                println!("Use it with:\nonboard val --template-url {}", t.url);
+            }
+            None => {
+                println!("ERROR: no template selected");
+                exit(1);
+            }
+        }
+    }
+}
+
+/// fetch the index of known templates from the genesis repo
+pub fn fetch_templates(
+    github_org: &Option<String>,
+    repo: &Option<String>,
+) -> Result<Vec<TemplateEntry>, Error> {
+    let url = format!("{}templates/index.json", files_cmd::raw_repo_url(github_org, repo));
+    let templates: Vec<TemplateEntry> = reqwest::blocking::get(&url)?.json()?;
+    Ok(templates)
+}
+
+/// find a template by name, and list the known ones if it does not exist
+pub fn find_template(templates: &[TemplateEntry], name: &str) -> Option<TemplateEntry> {
+    let found = templates.iter().find(|t| t.name == name).cloned();
+    if found.is_none() {
+        println!("Template {} not found, available templates:", name);
+        templates
+            .iter()
+            .for_each(|t| println!("{}: {}", t.name, t.description));
+    }
+    found
+}
+
+/// resolve a template name from the index into the url to configure from
+pub fn template_url_from_name(
+    name: &str,
+    github_org: &Option<String>,
+    repo: &Option<String>,
+) -> Result<Url, Error> {
+    let templates = fetch_templates(github_org, repo)?;
+    match find_template(&templates, name) {
+        Some(t) => Ok(Url::parse(&t.url)?),
+        None => Err(Error::msg(format!("no template named {}", name))),
+    }
+}
+
+/// pick a template from a list on the terminal, errors without a terminal to ask on
+fn pick_template(templates: &[TemplateEntry]) -> Result<Option<TemplateEntry>, Error> {
+    let items: Vec<String> = templates
+        .iter()
+        .map(|t| format!("{}: {}", t.name, t.description))
+        .collect();
+    let index = Select::new()
+        .with_prompt("Choose a template to configure from")
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .map_err(|e| Error::msg(format!("could not ask for a template, pass --name instead: {}", e)))?;
+    Ok(index.map(|i| templates[i].clone()))
+}
@@ -0,0 +1,67 @@
+//! `validate` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::manifest;
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process::exit};
+
+/// `validate` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct ValidateCmd {
+    #[options(help = "path of the account.json to validate, or a directory of them")]
+    manifest: Option<PathBuf>,
+    #[options(help = "validate the manifests of a directory in parallel")]
+    parallel_verify: bool,
+    #[options(help = "manifests validated at once with --parallel-verify, defaults to 4")]
+    jobs: Option<usize>,
+}
+
+impl Runnable for ValidateCmd {
+    fn run(&self) {
+        let path = self.manifest.clone().unwrap_or_else(|| {
+            println!("ERROR: pass the account.json to check with --manifest, exiting.");
+            exit(1);
+        });
+
+        if path.is_dir() {
+            let paths = manifest::manifests_in_dir(&path).unwrap_or_else(|e| {
+                println!("ERROR: {:?}, exiting.", e);
+                exit(1);
+            });
+            let jobs = if self.parallel_verify {
+                self.jobs.unwrap_or(manifest::DEFAULT_VERIFY_JOBS)
+            } else {
+                1
+            };
+            let results = manifest::validate_manifests(paths, jobs);
+            let failed = results
+                .iter()
+                .filter(|(p, checks)| match checks {
+                    Ok(c) => !manifest::print_report(p, c),
+                    Err(e) => {
+                        println!("Manifest {:?}\nFAIL: could not read it, {}", p, e);
+                        true
+                    }
+                })
+                .count();
+            println!("\n{} of {} manifests passed", results.len() - failed, results.len());
+            if failed > 0 {
+                exit(1);
+            }
+            return;
+        }
+
+        match manifest::validate_manifest(&path) {
+            Ok(checks) => {
+                if !manifest::print_report(&path, &checks) {
+                    exit(1);
+                }
+            }
+            Err(e) => {
+                println!("ERROR: could not read manifest {:?}, message: {:?}", &path, e);
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! `verify-keystore` subcommand
+
+#![allow(clippy::never_loop)]
+
+use crate::{
+    home,
+    key_store::{self, ChecksumStatus},
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process::exit};
+
+/// `verify-keystore` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct VerifyKeystoreCmd {
+    #[options(help = "node home with the key_store.json")]
+    home: Option<PathBuf>,
+}
+
+impl Runnable for VerifyKeystoreCmd {
+    fn run(&self) {
+        let home_path = home::home_override(&self.home).unwrap_or_else(home::default_home);
+        match key_store::verify_checksum(&home_path) {
+            Ok(ChecksumStatus::Verified) => println!("key_store.json matches its checksum"),
+            Ok(ChecksumStatus::Missing) => println!(
+                "WARN: no {} in {:?}, the key store was written by an older onboard. Run `onboard restore-keystore --force` to write one.",
+                key_store::CHECKSUM_FILE,
+                &home_path
+            ),
+            Err(e) => {
+                println!("ERROR: {:#}, exiting.", e);
+                exit(1);
+            }
+        }
+    }
+}
@@ -3,16 +3,67 @@
 #![allow(clippy::never_loop)]
 
 use super::WizCmd;
+use crate::manifest::TEMPLATE_SCHEMA_VERSION;
 use abscissa_core::{Command, Options, Runnable};
+use diem_global_constants::VDF_SECURITY_PARAM;
+use ol_fixtures::get_bundled_genesis;
+use ol_keys::scheme::{KEYS_PER_ACCOUNT, SCHEME_VERSION};
+use serde::Serialize;
 
 /// `version` subcommand
 #[derive(Command, Debug, Default, Options)]
-pub struct VersionCmd {}
+pub struct VersionCmd {
+    #[options(help = "print the key scheme, genesis, and VDF versions as json")]
+    full: bool,
+}
+
+/// Everything the binary was built with which affects onboarding compatibility
+#[derive(Serialize)]
+pub struct FullVersion {
+    /// name of the binary
+    pub name: String,
+    /// crate version
+    pub version: String,
+    /// version of the ol-keys derivation scheme
+    pub key_scheme_version: u64,
+    /// child keys derived per account
+    pub keys_per_account: u64,
+    /// newest template schema the wizard reads
+    pub template_schema_version: u64,
+    /// waypoint of the genesis bundled for mainnet, if any
+    pub bundled_genesis_waypoint: Option<String>,
+    /// VDF difficulty from the current NODE_ENV
+    pub vdf_difficulty: u64,
+    /// VDF security parameter
+    pub vdf_security_param: u16,
+}
 
+impl FullVersion {
+    /// collect the versions compiled into this binary
+    pub fn current() -> Self {
+        FullVersion {
+            name: WizCmd::name().to_owned(),
+            version: WizCmd::version().to_owned(),
+            key_scheme_version: SCHEME_VERSION,
+            keys_per_account: KEYS_PER_ACCOUNT,
+            template_schema_version: TEMPLATE_SCHEMA_VERSION,
+            bundled_genesis_waypoint: get_bundled_genesis(1).map(|(_, w)| w.trim().to_owned()),
+            vdf_difficulty: miner::delay::delay_difficulty(),
+            vdf_security_param: VDF_SECURITY_PARAM,
+        }
+    }
+}
 
 impl Runnable for VersionCmd {
     /// Print version message
     fn run(&self) {
+        if self.full {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&FullVersion::current()).unwrap()
+            );
+            return;
+        }
         println!("{} {}", WizCmd::name(), WizCmd::version());
     }
 }
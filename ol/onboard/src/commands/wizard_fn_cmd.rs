@@ -46,7 +46,7 @@ impl Runnable for FnWizardCmd {
                 output_path,
                 &self.github_org,
                 &self.repo,
-            );
+            ).expect("could not fetch genesis files");
             status_ok!("\nGenesis OK", "\n...........................\n");
         }
 
@@ -2,7 +2,7 @@
 
 #![allow(clippy::never_loop)]
 
-use super::files_cmd;
+use super::{files_cmd, wizard_val_cmd::url_with_port};
 
 use crate::entrypoint;
 use crate::prelude::app_config;
@@ -49,9 +49,9 @@ pub struct ForkCmd {
     autopay_file: Option<PathBuf>,
     #[options(help = "An upstream peer to use in 0L.toml")]
     upstream_peer: Option<Url>,
-    #[options(help = "If validator is building from source")]
+    #[options(help = "root of the libra repo, if the validator builds from source, with the stdlib built")]
     source_path: Option<PathBuf>,
-    #[options(short = "w", help = "If validator is building from source")]
+    #[options(short = "w", help = "waypoint to use in 0L.toml and the key store")]
     waypoint: Option<Waypoint>,
     #[options(short = "e", help = "If validator is building from source")]
     epoch: Option<u64>,
@@ -69,13 +69,16 @@ impl Runnable for ForkCmd {
         // Get credentials from prompt
         let (authkey, account, wallet) = wallet::get_account_from_prompt();
 
-        let mut upstream = self.upstream_peer.clone().unwrap_or_else(|| {
+        let upstream = self.upstream_peer.clone().unwrap_or_else(|| {
             self.template_url.clone().unwrap_or_else(|| {
                 println!("ERROR: Must set a URL to query chain. Use --upstream-peer or --template-url. Exiting.");
                 exit(1);
             })
         });
-        upstream.set_port(Some(8080)).unwrap();
+        let upstream = url_with_port(&upstream, 8080).unwrap_or_else(|e| {
+            println!("ERROR: {}, exiting.", e);
+            exit(1)
+        });
         println!("Setting upstream peer URL to: {:?}", &upstream);
 
         let mut wp = self.waypoint.clone();
@@ -95,7 +98,11 @@ impl Runnable for ForkCmd {
             &self.source_path,
             None,
             None,
-        );
+        )
+        .unwrap_or_else(|e| {
+            println!("ERROR: could not initialize app configs, exiting. Message: {:?}", e);
+            exit(1)
+        });
         let home_path = &app_config.workspace.node_home;
         let base_waypoint = app_config.chain_info.base_waypoint.clone();
         dbg!(&base_waypoint);
@@ -103,8 +110,10 @@ impl Runnable for ForkCmd {
         status_ok!("\nApp configs written", "\n...........................\n");
 
         if let Some(url) = &self.template_url {
-            let mut url = url.to_owned();
-            url.set_port(Some(3030)).unwrap(); //web port
+            let url = url_with_port(url, 3030).unwrap_or_else(|e| {
+                println!("ERROR: {}, exiting.", e);
+                exit(1)
+            }); //web port
             save_template(&url.join("account.json").unwrap(), home_path);
             // get autopay
             status_ok!("\nTemplate saved", "\n...........................\n");
@@ -132,7 +141,8 @@ impl Runnable for ForkCmd {
         // fetching the genesis files from genesis-archive, will override the path for prebuilt genesis.
         let mut prebuilt_genesis_path = self.prebuilt_genesis.clone();
         if self.fetch_git_genesis {
-            files_cmd::get_files(home_path.clone(), &self.github_org, &self.repo);
+            files_cmd::get_files(home_path.clone(), &self.github_org, &self.repo)
+                .expect("could not fetch genesis files");
             status_ok!(
                 "\nDownloaded genesis files",
                 "\n...........................\n"
@@ -168,7 +178,10 @@ impl Runnable for ForkCmd {
 
         if !self.skip_mining {
             // Mine Block
-            miner::block::write_genesis(&app_config);
+            miner::block::write_genesis(&app_config).map(|_| ()).unwrap_or_else(|e| {
+                println!("ERROR: could not mine block zero, exiting. Message: {:?}", e);
+                exit(1);
+            });
             status_ok!(
                 "\nGenesis proof complete",
                 "\n...........................\n"
@@ -213,9 +226,15 @@ pub fn get_autopay_batch(
         &file_path.clone().unwrap_or(home_path.join(file_name)),
         Some(starting_epoch.clone()),
         None,
+        None,
     )
     .unwrap();
-    let script_vec = autopay_batch_cmd::process_instructions(instr_vec.clone());
+    let script_vec = autopay_batch_cmd::process_instructions(instr_vec.clone())
+        .unwrap_or_else(|errors| {
+            autopay_batch_cmd::print_instruction_errors(&errors);
+            println!("Fix the autopay instructions and run again, exiting.");
+            exit(1)
+        });
     let url = cfg.what_url(false);
     let mut tx_params = submit_tx::get_tx_params_from_toml(
         cfg.to_owned(),
@@ -7,7 +7,7 @@ use ol_types::block::Block;
 use miner::{delay, block::write_genesis};
 use ol_types::config::AppCfg;
 use abscissa_core::{Command, Options, Runnable};
-use std::{path::PathBuf};
+use std::{path::PathBuf, process::exit};
 use ol_types::account;
 /// `user wizard` subcommand
 #[derive(Command, Debug, Default, Options)]
@@ -48,7 +48,13 @@ fn wizard(path: PathBuf, block_zero: &Option<PathBuf>) {
     if let Some(block_path) = block_zero {
         block = Block::parse_block_file(block_path.to_owned());
     } else {
-        block = write_genesis(&app_cfg);
+        block = match write_genesis(&app_cfg) {
+            Ok((block, _difficulty)) => block,
+            Err(e) => {
+                println!("ERROR: could not mine block zero, exiting. Message: {:?}", e);
+                exit(1);
+            }
+        };
     }
 
     // Create Manifest
@@ -2,245 +2,1881 @@
 
 #![allow(clippy::never_loop)]
 
-use super::files_cmd;
-use crate::entrypoint;
+use super::{files_cmd, templates_cmd};
+use super::CONFIG_FILE;
+use crate::{
+    build_genesis,
+    cancel::{self, CancelToken},
+    chain_info, entrypoint, home, ip, key_store, manifest, permissions, precheck, read_genesis, systemd,
+    manifest::ManifestCheck,
+    tarball::{self, TarballEntry},
+};
 use crate::prelude::app_config;
-use abscissa_core::{status_info, status_ok, Command, Options, Runnable};
+use crate::{status, status_info, status_ok};
+use abscissa_core::{Command, Options, Runnable};
+use anyhow::{bail, Context, Error};
+use dialoguer::Confirm;
 use diem_genesis_tool::ol_node_files;
-use diem_types::{transaction::SignedTransaction, waypoint::Waypoint};
+use diem_global_constants::CONSENSUS_KEY;
+use diem_crypto::{
+    ed25519::Ed25519PublicKey,
+    hash::{CryptoHash, HashValue},
+    test_utils::KeyPair,
+    ValidCryptoMaterialStringExt,
+};
+use diem_types::{
+    transaction::{authenticator::AuthenticationKey, SignedTransaction, Transaction},
+    waypoint::Waypoint,
+};
 use diem_wallet::WalletLibrary;
-use ol::{commands::init_cmd, config::AppCfg};
+use miner::delay;
+use diem_types::account_address::AccountAddress;
+use ol::{commands::init_cmd, config::AppCfg, node::client::make_client};
 use ol_fixtures::get_test_genesis_blob;
 use ol_keys::{scheme::KeyScheme, wallet};
 use ol_types::block::Block;
-use ol_types::config::IS_TEST;
-use ol_types::{account::ValConfigs, config::TxType, pay_instruction::PayInstruction};
+use ol_types::config::{bootstrap_waypoint_from_upstream, parse_toml, CONTACT_METADATA_KEY, IS_TEST};
+use ol_types::{
+    account::{OperatorConfigs, ValConfigs, OPERATOR_FILE},
+    config::TxType,
+    pay_instruction::{format_coin, PayInstruction},
+};
 use reqwest::Url;
+use std::collections::BTreeMap;
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
 use std::process::exit;
-use std::{fs::File, io::Write, path::PathBuf};
+use std::time::Instant;
+use std::{fs::File, io::Write, path::{Path, PathBuf}};
 use txs::{commands::autopay_batch_cmd, submit_tx};
 
 /// `validator wizard` subcommand
-#[derive(Command, Debug, Default, Options)]
+#[derive(Command, Debug, Default)]
 pub struct ValWizardCmd {
-    #[options(
-        short = "a",
-        help = "where to output the account.json file, defaults to node home"
-    )]
-    output_path: Option<PathBuf>,
-    #[options(help = "explicitly set home path instead of answer in wizard, for CI usually")]
-    home_path: Option<PathBuf>,
-    #[options(help = "id of the chain")]
-    chain_id: Option<u8>,
-    #[options(help = "github org of genesis repo")]
-    github_org: Option<String>,
-    #[options(help = "repo with with genesis transactions")]
-    repo: Option<String>,
-    #[options(help = "use a genesis file instead of building")]
-    prebuilt_genesis: Option<PathBuf>,
-    #[options(help = "fetching genesis blob from github")]
-    fetch_git_genesis: bool,
-    #[options(help = "skip mining a block zero")]
-    skip_mining: bool,
-    #[options(short = "u", help = "template account.json to configure from")]
-    template_url: Option<Url>,
-    #[options(help = "autopay file if instructions are to be sent")]
-    autopay_file: Option<PathBuf>,
-    #[options(help = "An upstream peer to use in 0L.toml")]
-    upstream_peer: Option<Url>,
-    #[options(help = "If validator is building from source")]
-    source_path: Option<PathBuf>,
-    #[options(short = "w", help = "If validator is building from source")]
-    waypoint: Option<Waypoint>,
-    #[options(short = "e", help = "If validator is building from source")]
-    epoch: Option<u64>,
-    #[options(help = "For testing in ci, use genesis.blob fixtures")]
-    ci: bool,
-    #[options(help = "Used only on genesis ceremony")]
-    genesis_ceremony: bool,
+    opts: WizardOptions,
 }
 
-impl Runnable for ValWizardCmd {
-    fn run(&self) {
-        // Note. `onboard` command DOES NOT READ CONFIGS FROM 0L.toml
+// the flags are the options of the wizard, which the tools running it with run_wizard share
+impl Options for ValWizardCmd {
+    fn parse<S: AsRef<str>>(parser: &mut gumdrop::Parser<S>) -> Result<Self, gumdrop::Error> {
+        WizardOptions::parse(parser).map(|opts| ValWizardCmd { opts })
+    }
 
-        status_info!(
-            "\nValidator Config Wizard.", "Next you'll enter your mnemonic and some other info to configure your validator node and on-chain account. If you haven't yet generated keys, run the standalone keygen tool with 'ol keygen'.\n\nYour first 0L proof-of-work will be mined now. Expect this to take up to 15 minutes on modern CPUs.\n"
-        );
+    fn parse_command<S: AsRef<str>>(name: &str, parser: &mut gumdrop::Parser<S>) -> Result<Self, gumdrop::Error> {
+        WizardOptions::parse_command(name, parser).map(|opts| ValWizardCmd { opts })
+    }
 
-        let entry_args = entrypoint::get_args();
+    fn usage() -> &'static str {
+        WizardOptions::usage()
+    }
+
+    fn command_usage(command: &str) -> Option<&'static str> {
+        WizardOptions::command_usage(command)
+    }
+
+    fn command_list() -> Option<&'static str> {
+        WizardOptions::command_list()
+    }
+}
 
-        // Get credentials from prompt
-        let (authkey, account, wallet) = wallet::get_account_from_prompt();
+/// Options of a wizard run, the flags of `onboard val`.
+///
+/// The ones which only print and exit, and the intro banner, are only read by `onboard val`,
+/// not by run_wizard.
+#[derive(Clone, Debug, Default, Options)]
+pub struct WizardOptions {
+    /// where to output the account.json file, defaults to node home
+    #[options(short = "a")]
+    pub output_path: Option<PathBuf>,
+    /// write the wizard's status messages to this file instead of the terminal
+    pub status_log: Option<PathBuf>,
+    /// print the phases of the wizard in the order they run, and exit
+    pub list_phases: bool,
+    /// run only this phase, can be repeated. The keys are always derived, and the files of the phases skipped must be in the home
+    pub only_phase: Vec<String>,
+    /// explicitly set home path instead of answer in wizard, for CI usually. Defaults to $OL_NODE_HOME if set
+    pub home_path: Option<PathBuf>,
+    /// regenerate configs from the existing key_store.json, without the mnemonic or mining
+    pub reuse_keys: bool,
+    /// operator of a delegated validator: configure the node for --owner-account, and write operator.json for the owner instead of account.json
+    pub operator_only: bool,
+    /// account of the owner the --operator-only node runs for
+    pub owner_account: Option<AccountAddress>,
+    /// owner of a delegated validator: write account.json for the operator in --operator-file, without configuring a node
+    pub owner_only: bool,
+    /// appended to the account for the operator namespace of the key store and node config, defaults to -oper
+    #[options(no_short)]
+    pub namespace_suffix: Option<String>,
+    /// operator.json written by the operator's --operator-only run
+    pub operator_file: Option<PathBuf>,
+    /// rebuild the node from an existing account.json, reusing its block zero and autopay
+    pub from_account_json: Option<PathBuf>,
+    /// auth key of the account, for --reuse-keys when 0L.toml is missing
+    pub auth_key: Option<AuthenticationKey>,
+    /// prompt for the BIP39 passphrase of the mnemonic
+    pub mnemonic_passphrase: bool,
+    /// read the mnemonic from this file descriptor number, or named pipe path, instead of the prompt
+    #[options(no_short)]
+    pub mnemonic_fd: Option<String>,
+    /// generate a new mnemonic and onboard with it, instead of entering one
+    pub generate_mnemonic: bool,
+    /// derivation index of the account to configure, defaults to 0
+    pub account_index: Option<u64>,
+    /// print the closing guidance for the node already configured in the home path, and exit
+    pub print_next_steps_only: bool,
+    /// print the accounts at the first N derivation indexes of the mnemonic, and exit
+    pub list_derived_accounts: Option<u64>,
+    /// print the role, child number, and public key of each derived key
+    pub show_key_derivation: bool,
+    /// key=value note to add to the metadata of 0L.toml, can be repeated
+    pub profile_note: Vec<String>,
+    /// email, matrix handle, or URL the network can reach the operator at, written to 0L.toml and account.json
+    pub contact: Option<String>,
+    /// id of the chain
+    pub chain_id: Option<u8>,
+    /// continue with a chain id which is not one of the known networks
+    pub allow_unknown_chain: bool,
+    /// github org of genesis repo
+    pub github_org: Option<String>,
+    /// repo with with genesis transactions
+    pub repo: Option<String>,
+    /// use a genesis file instead of building
+    pub prebuilt_genesis: Option<PathBuf>,
+    /// fetching genesis blob from github
+    pub fetch_git_genesis: bool,
+    /// fetch the genesis files from github://ORG/REPO, or the https:// URL of a directory with them, instead of --github-org and --repo
+    pub genesis_source: Option<String>,
+    /// recompute the waypoint of the genesis.blob and check it against genesis_waypoint
+    pub verify_genesis: bool,
+    /// build genesis.blob from a directory of genesis contributions and its layout.toml, instead of fetching it
+    pub force_rebuild_genesis: Option<PathBuf>,
+    /// write the waypoint the node is configured with to this file
+    pub genesis_waypoint_out: Option<PathBuf>,
+    /// if fetching genesis fails, use the genesis bundled in this binary
+    pub allow_bundled_genesis: bool,
+    /// mine even if the difficulty isn't the one the chain id expects
+    pub allow_difficulty_mismatch: bool,
+    /// skip mining a block zero
+    pub skip_mining: bool,
+    /// report the CPU and RAM of the host with the mining time, e.g. for a support ticket
+    #[options(no_short)]
+    pub report_hardware: bool,
+    /// don't show the intro banner, for scripted runs
+    pub no_intro: bool,
+    /// custom text for the intro banner
+    pub intro_text: Option<String>,
+    /// template account.json to configure from
+    #[options(short = "u")]
+    pub template_url: Option<Url>,
+    /// use the port of the template URL as given, instead of the 3030 web monitor port
+    pub skip_template_port_rewrite: bool,
+    /// download the template again, even if template.json was already saved
+    pub refresh_template: bool,
+    /// name of a community template from the templates index, instead of --template-url
+    pub template_name: Option<String>,
+    /// autopay file (json or csv) if instructions are to be sent, or a directory of them to merge
+    pub autopay_file: Option<PathBuf>,
+    /// name of the autopay file looked up in the node home without --autopay-file or a template, defaults to autopay_batch.json
+    #[options(no_short)]
+    pub autopay_default_name: Option<String>,
+    /// epoch which relative autopay instructions start from, instead of the config's base epoch
+    pub base_epoch: Option<u64>,
+    /// epochs to shift all autopay instructions by, for a template reused on another chain. Negative starts earlier, at zero at most
+    pub autopay_start_offset: Option<i64>,
+    /// maximum percent of balance, or of change, the autopay instructions can commit, defaults to 50
+    pub autopay_percent_cap: Option<f64>,
+    /// octal mode of all the files the wizard writes, e.g. 640, instead of 600 for key material and 644 for the others
+    #[options(no_short)]
+    pub file_mode: Option<String>,
+    /// sign autopay instructions above the percent cap, overwrite files without --confirm-destructive asking, and configure a home a running node uses
+    pub force: bool,
+    /// list the files of a previous run which would be overwritten, and ask before continuing
+    pub confirm_destructive: bool,
+    /// sign only the valid autopay instructions, skipping the invalid ones
+    pub continue_on_autopay_error: bool,
+    /// warn about autopay destinations which are not accounts on the upstream peer
+    pub validate_autopay_destinations: bool,
+    /// sign the autopay instructions without reviewing them first
+    pub no_autopay_review: bool,
+    /// sequence number to start signing autopay txs from, defaults to the account's sequence number on the upstream peer
+    pub autopay_seq_start: Option<u64>,
+    /// an upstream peer to use in 0L.toml
+    pub upstream_peer: Option<Url>,
+    /// json array of curated peer URLs for the upstream nodes of 0L.toml, the first reachable one is queried
+    #[options(no_short)]
+    pub upstream_from_file: Option<PathBuf>,
+    /// connect to the JSON-RPC of the nodes over https
+    pub rpc_tls: bool,
+    /// reject template, upstream, seed peer, genesis, chain info, and report URLs which aren't https
+    #[options(no_short)]
+    pub strict_url_scheme: bool,
+    /// root of the libra repo, if the validator builds from source, with the stdlib built
+    pub source_path: Option<PathBuf>,
+    /// waypoint to use in 0L.toml and the key store
+    #[options(short = "w")]
+    pub waypoint: Option<Waypoint>,
+    /// file with the waypoint, instead of --waypoint
+    pub waypoint_file: Option<PathBuf>,
+    /// epoch of the waypoint, passed with --waypoint or --waypoint-file
+    #[options(short = "e")]
+    pub epoch: Option<u64>,
+    /// fetch the epoch and waypoint from the upstream peer, instead of --epoch and --waypoint
+    pub epoch_from_chain: bool,
+    /// path or URL of a signed chain_info.json with the epoch and waypoint, signature in chain_info.json.sig
+    pub chain_info: Option<String>,
+    /// hex ed25519 public key of the genesis authority signing --chain-info
+    pub chain_info_pubkey: Option<String>,
+    /// use a --chain-info which is unsigned or can't be verified
+    pub insecure_chain_info: bool,
+    /// save the hashes of the signed autopay txs to autopay_tx_hashes.json
+    pub save_tx_hashes: bool,
+    /// write account.json without checking it for private keys, never share it then
+    pub manifest_include_private: bool,
+    /// also write the manifest as BCS to account.bcs
+    pub bcs_manifest: bool,
+    /// registration endpoint to POST the account.json to once it's written
+    pub report_to: Option<Url>,
+    /// check the files written agree with each other at the end, on by default unless --ci, a swarm, or --only-phase
+    pub verify_after_write: bool,
+    /// write the configs, key store, block zero and manifest to this .tar.gz at the end, to move them to the node
+    pub bundle_out: Option<PathBuf>,
+    /// write a 0l-node.service systemd unit for the node
+    pub write_systemd_unit: bool,
+    /// file or directory to write the systemd unit to, defaults to the node home
+    pub systemd_unit_path: Option<PathBuf>,
+    /// path of the diem-node binary in the systemd unit, defaults to /usr/local/bin/diem-node
+    pub node_bin: Option<PathBuf>,
+    /// ip address of the node, instead of answering in the wizard
+    pub ip: Option<Ipv4Addr>,
+    /// detect the public ip address of the node, unless --ip is set
+    pub autodetect_ip: bool,
+    /// ip-echo service used by --autodetect-ip
+    pub ip_service: Option<Url>,
+    /// check the local clock against the upstream peer before signing txs
+    pub check_time_sync: bool,
+    /// max clock skew in seconds tolerated by --check-time-sync, defaults to 30
+    pub max_clock_skew: Option<u64>,
+    /// minimum free bytes on the disk of the node home, defaults to 1GB
+    pub min_free_disk: Option<u64>,
+    /// run as root without asking, the files written will be owned by root
+    #[options(no_short)]
+    pub allow_root: bool,
+    /// for testing in ci, use genesis.blob fixtures
+    pub ci: bool,
+    /// used only on genesis ceremony
+    pub genesis_ceremony: bool,
+}
+
+/// A run of the wizard phases with the options
+pub struct Wizard<'a> {
+    /// options of the run
+    pub opts: &'a WizardOptions,
+    /// run against a local swarm, the --swarm-path of `onboard`
+    pub swarm: bool,
+}
+
+/// Phases of the wizard, in the order run_phases runs them
+pub const PHASES: [&str; 9] = [
+    "derive-keys",
+    "write-config",
+    "sign-autopay",
+    "write-keys",
+    "write-node-files",
+    "write-operator-file",
+    "mine-block-zero",
+    "write-manifest",
+    "write-bundle",
+];
+
+/// Credentials of the account being onboarded, from the first phase of the wizard
+pub struct WizardKeys {
+    /// wallet of the mnemonic, None when reusing the key store
+    pub wallet: Option<WalletLibrary>,
+    /// derivation index of the account
+    pub account_index: u64,
+    /// auth key of the account
+    pub authkey: AuthenticationKey,
+    /// the account being onboarded
+    pub account: AccountAddress,
+    /// node home passed or implied by the options, None to ask for it
+    pub config_home: Option<PathBuf>,
+    /// manifest the node is rebuilt from, with --from-account-json
+    pub source_manifest: Option<ValConfigs>,
+}
+
+/// Configs written by the wizard, which the later phases build on
+pub struct WizardConfig {
+    /// the 0L.toml written
+    pub app_config: AppCfg,
+    /// template the account is configured from
+    pub template_url: Option<Url>,
+    /// upstream peer to query the chain
+    pub upstream_peer: Option<Url>,
+    /// epoch relative autopay instructions start from
+    pub base_epoch: Option<u64>,
+    /// lockfile of the home, removed once the run is done with the configs
+    pub lock: Option<cancel::HomeLock>,
+}
+
+impl Wizard<'_> {
+    /// Checks the chain id, then gets the credentials from the prompt, a new mnemonic, or the key store.
+    pub fn derive_keys(&self, token: &CancelToken) -> Result<WizardKeys, Error> {
+        token.check()?;
+        precheck::check_not_root(self.opts.allow_root)?;
+        precheck::check_chain_id(self.opts.chain_id.unwrap_or(1), self.opts.allow_unknown_chain)?;
+
+        // a node being rebuilt keeps the proof and autopay of its manifest
+        let source_manifest = match &self.opts.from_account_json {
+            Some(path) => {
+                if self.opts.reuse_keys {
+                    bail!("--from-account-json needs the mnemonic, it can't be used with --reuse-keys");
+                }
+                let checks = manifest::validate_manifest(path)
+                    .with_context(|| format!("could not read manifest {:?}", path))?;
+                if !manifest::print_report(path, &checks) {
+                    bail!("{:?} doesn't pass validation", path);
+                }
+                Some(manifest::read_manifest(path)?)
+            }
+            None => None,
+        };
 
-        let upstream_peer = if *&self.genesis_ceremony {
+        // Get credentials from prompt, or from a mnemonic generated now.
+        // When reusing the key store there is no mnemonic, and nothing is signed.
+        let wallet = if self.opts.reuse_keys {
             None
+        } else if self.opts.generate_mnemonic {
+            Some(generate_mnemonic()?)
+        } else if let Some(source) = &self.opts.mnemonic_fd {
+            let passphrase = if self.opts.mnemonic_passphrase {
+                wallet::read_passphrase_from_prompt()
+            } else {
+                "".to_owned()
+            };
+            let (_, account, wallet) = wallet::get_account_from_fd(source, &passphrase)
+                .context("could not use the mnemonic of --mnemonic-fd")?;
+            if self.opts.mnemonic_passphrase {
+                confirm_passphrase_account(account)?;
+            }
+            Some(wallet)
+        } else if self.opts.mnemonic_passphrase {
+            let (_, account, wallet) = wallet::try_account_from_prompt_with_passphrase()?;
+            confirm_passphrase_account(account)?;
+            Some(wallet)
         } else {
-            let mut upstream = self.upstream_peer.clone().unwrap_or_else(|| {
-              self.template_url.clone().unwrap_or_else(|| {
-                  print!("ERROR: Must set a URL to query chain. Use --upstream-peer of --template-url, exiting.");
-                  exit(1)  
-              })
-            });
-            upstream.set_port(Some(8080)).unwrap();
-            println!("Setting upstream peer URL to: {:?}", &upstream.as_str());
-            Some(upstream)
+            Some(wallet::try_account_from_prompt()?.2)
+        };
+        let account_index = self.opts.account_index.unwrap_or(0);
+        // the key store being reused must be in the node home, so don't ask for another one
+        let home_override = home::try_home_override(&self.opts.home_path)?;
+        let config_home = match home_override {
+            None if self.opts.reuse_keys => Some(home::try_default_home()?),
+            home_override => home_override,
+        };
+        let (authkey, account) = match &wallet {
+            Some(w) => {
+                let authkey = KeyScheme::new_with_index(w, account_index).derived_auth_key();
+                (authkey, authkey.derived_address())
+            }
+            None => reused_account(config_home.as_ref().unwrap(), &self.opts.auth_key, self.namespace_suffix())?,
+        };
+        // the operator's node is configured under the owner's account, the keys are the operator's own
+        let account = match (self.opts.operator_only, self.opts.owner_account) {
+            (true, Some(owner)) => owner,
+            (true, None) => bail!("--operator-only needs the account of the owner, pass --owner-account"),
+            (false, _) => account,
         };
+        if self.opts.show_key_derivation {
+            match &wallet {
+                Some(w) => print_key_derivation(&KeyScheme::new_with_index(w, account_index)),
+                None => println!("WARN: --show-key-derivation needs the mnemonic, the keys are reused"),
+            }
+        }
+        if let Some(m) = &source_manifest {
+            if m.ow_human_name.parse::<AccountAddress>().ok() != Some(account) {
+                bail!(
+                    "the manifest is for account {}, but the mnemonic is for {}",
+                    m.ow_human_name,
+                    account
+                );
+            }
+        }
 
-        let app_config = AppCfg::init_app_configs(
+        Ok(WizardKeys {
+            wallet,
+            account_index,
             authkey,
             account,
+            config_home,
+            source_manifest,
+        })
+    }
+
+    /// Writes 0L.toml, runs the prechecks of the host, and saves the template.
+    pub fn write_config(&self, token: &CancelToken, keys: &WizardKeys) -> Result<WizardConfig, Error> {
+        token.check()?;
+        let template_url = match &self.opts.template_name {
+            Some(name) => Some(
+                templates_cmd::template_url_from_name(name, &self.opts.github_org, &self.opts.repo)
+                    .with_context(|| format!("could not resolve template {}", name))?,
+            ),
+            None => self.opts.template_url.clone(),
+        };
+        if let Some(url) = &template_url {
+            check_template_url(url)?;
+        }
+
+        let file_peers = match &self.opts.upstream_from_file {
+            Some(path) if !self.builds_genesis() => {
+                let peers = files_cmd::read_peer_file(path)?
+                    .iter()
+                    .map(|p| url_with_port(p, 8080))
+                    .collect::<Result<Vec<Url>, Error>>()
+                    .with_context(|| format!("{:?} has a malformed peer", path))?;
+                if self.opts.strict_url_scheme {
+                    for peer in &peers {
+                        check_strict_scheme("a peer of --upstream-from-file", peer.as_str())?;
+                    }
+                }
+                println!("{} peers read from {:?}", peers.len(), path);
+                Some(peers)
+            }
+            _ => None,
+        };
+        // the chain is queried through the first peer of the file which is up
+        let reachable_peer = file_peers.as_ref().map(|peers| {
+            precheck::first_reachable_peer(peers).unwrap_or_else(|| {
+                println!("WARN: none of the peers of the peer file are reachable, using the first one");
+                peers[0].clone()
+            })
+        });
+
+        // seed peers published with genesis remove the need for --upstream-peer
+        let seed_peers = if self.opts.fetch_git_genesis
+            && !self.builds_genesis()
+            && self.opts.upstream_peer.is_none()
+            && template_url.is_none()
+            && file_peers.is_none()
+        {
+            files_cmd::get_seed_peers(&self.opts.github_org, &self.opts.repo)
+                .unwrap_or_else(|e| {
+                    println!("WARN: could not fetch seed peers, message: {:?}", e);
+                    None
+                })
+                .map(|peers| {
+                    // get_seed_peers already skipped the entries which aren't URLs, this skips the ones without a host
+                    peers
+                        .iter()
+                        .filter_map(|p| match url_with_port(p, 8080) {
+                            Ok(url) => Some(url),
+                            Err(e) => {
+                                println!("WARN: skipping the seed peer {}: {}", p, e);
+                                None
+                            }
+                        })
+                        .collect::<Vec<Url>>()
+                })
+                .filter(|peers| !peers.is_empty())
+        } else {
+            None
+        };
+        if self.opts.strict_url_scheme {
+            for peer in seed_peers.iter().flatten() {
+                check_strict_scheme("the seed peer", peer.as_str())?;
+            }
+        }
+
+        let upstream_peer = if self.builds_genesis() {
+            None
+        } else {
+            let upstream = self
+                .opts
+                .upstream_peer
+                .clone()
+                .or_else(|| template_url.clone())
+                .or_else(|| reachable_peer.clone())
+                .or_else(|| seed_peers.as_ref().and_then(|p| p.first().cloned()))
+                .ok_or_else(|| Error::msg("Must set a URL to query chain. Use --upstream-peer, --template-url, --upstream-from-file, or --fetch-git-genesis from a repo with seed peers"))?;
+            let upstream = url_with_port(&upstream, 8080)?;
+            if self.opts.strict_url_scheme {
+                // also the peer of the template, which isn't a flag
+                check_strict_scheme("the upstream peer", upstream.as_str())?;
+            }
+            println!("Setting upstream peer URL to: {:?}", &upstream.as_str());
+            Some(upstream)
+        };
+
+        let (epoch, waypoint) = if let Some(source) = &self.opts.chain_info {
+            if self.opts.epoch.is_some() || self.opts.waypoint.is_some() || self.opts.waypoint_file.is_some() || self.opts.epoch_from_chain {
+                bail!("--chain-info can't be used with --epoch, --waypoint, --waypoint-file, or --epoch-from-chain");
+            }
+            let pubkey = match &self.opts.chain_info_pubkey {
+                Some(k) => Some(Ed25519PublicKey::from_encoded_string(k.trim()).map_err(|e| {
+                    Error::msg(format!("--chain-info-pubkey is not a hex ed25519 public key: {:?}", e))
+                })?),
+                None => None,
+            };
+            let info = chain_info::read_chain_info(source, pubkey.as_ref(), self.opts.insecure_chain_info)
+                .with_context(|| format!("could not use the chain info {}", source))?;
+            println!("Using epoch {} and waypoint {} from the chain info", info.epoch, info.waypoint);
+            (Some(info.epoch), Some(info.waypoint))
+        } else if self.opts.epoch_from_chain {
+            if self.opts.epoch.is_some() || self.opts.waypoint.is_some() || self.opts.waypoint_file.is_some() {
+                bail!("--epoch-from-chain can't be used with --epoch, --waypoint, or --waypoint-file");
+            }
+            let url = upstream_peer
+                .as_ref()
+                .ok_or_else(|| Error::msg("--epoch-from-chain needs an upstream peer"))?;
+            let (e, w) = chain_epoch_waypoint(url)
+                .with_context(|| format!("could not fetch the epoch and waypoint from {}", url))?;
+            println!("Using epoch {} and waypoint {} from the chain", e, w);
+            (Some(e), Some(w))
+        } else if let Some(path) = &self.opts.waypoint_file {
+            if self.opts.waypoint.is_some() {
+                bail!("--waypoint-file can't be used with --waypoint");
+            }
+            (self.opts.epoch, Some(read_waypoint_file(path)?))
+        } else {
+            (self.opts.epoch, self.opts.waypoint)
+        };
+        // the chain info and the chain give both, but either can be forgotten on the command line
+        check_epoch_waypoint(epoch, waypoint)?;
+
+        let mut app_config = AppCfg::init_app_configs(
+            keys.authkey,
+            keys.account,
             &upstream_peer,
-            &self.home_path,
-            &self.epoch,
-            &self.waypoint,
-            &self.source_path,
-            None,
+            &keys.config_home,
+            &epoch,
+            &waypoint,
+            &self.opts.source_path,
             None,
-        );
+            ip::what_ip(
+                self.opts.ip.or_else(|| keys.source_manifest.as_ref().and_then(manifest_ip)),
+                self.opts.autodetect_ip,
+                &self.opts.ip_service,
+            ),
+        )
+        .context("could not initialize app configs")?;
+        if let Some(peers) = seed_peers {
+            app_config.profile.upstream_nodes = Some(peers);
+        }
+        if let (Some(mut peers), Some(first)) = (file_peers, &upstream_peer) {
+            // the peer queried goes first, the node tools query the upstream nodes in order
+            peers.retain(|p| p != first);
+            peers.insert(0, first.clone());
+            app_config.profile.upstream_nodes = Some(peers);
+        }
+        if !self.opts.profile_note.is_empty() {
+            app_config.metadata = parse_profile_notes(&self.opts.profile_note)?;
+        }
+        if let Some(contact) = &self.opts.contact {
+            app_config
+                .metadata
+                .insert(CONTACT_METADATA_KEY.to_owned(), contact.trim().to_owned());
+        }
+        if self.opts.rpc_tls {
+            app_config.profile.rpc_tls = true;
+        }
         let home_path = &app_config.workspace.node_home;
         let base_waypoint = app_config.chain_info.base_waypoint.clone();
+        // before the first write, so a full disk or another run leaves the home untouched
+        precheck::check_free_disk(
+            home_path,
+            self.opts.min_free_disk.unwrap_or(precheck::DEFAULT_MIN_FREE_DISK),
+        )?;
+        fs::create_dir_all(home_path)
+            .with_context(|| format!("could not create the node home {:?}", home_path))?;
+        let lock = cancel::HomeLock::new(home_path)?;
+        app_config.save_file().context("could not save the peers and profile of app configs")?;
+        self.set_file_modes(&[(home_path.join(CONFIG_FILE), false)])?;
+
+        if self.opts.check_time_sync {
+            match (&upstream_peer, base_waypoint) {
+                (Some(url), Some(wp)) => precheck::check_time_sync(
+                    url,
+                    wp,
+                    self.opts.max_clock_skew
+                        .unwrap_or(precheck::DEFAULT_MAX_CLOCK_SKEW_SECS),
+                )?,
+                _ => println!("WARN: --check-time-sync needs an upstream peer and a waypoint, skipping"),
+            }
+        }
+
+        // the operator keys are only written when there is a wallet to derive them from
+        if let (Some(w), Some(url), Some(wp)) = (&keys.wallet, &upstream_peer, base_waypoint) {
+            let operator = KeyScheme::new_with_index(w, keys.account_index).child_1_operator;
+            precheck::check_operator_account(
+                url,
+                wp,
+                operator.get_address(),
+                operator.get_authentication_key(),
+            )?;
+        }
 
         status_ok!("\nApp configs written", "\n...........................\n");
 
-        if let Some(url) = &self.template_url {
-            let mut url = url.to_owned();
-            url.set_port(Some(3030)).unwrap(); //web port
-            save_template(&url.join("account.json").unwrap(), home_path);
-            // get autopay
-            status_ok!("\nTemplate saved", "\n...........................\n");
+        if let Some(url) = &template_url {
+            let cached = home_path.join("template.json");
+            if cached.exists() && !self.opts.refresh_template {
+                println!(
+                    "Reusing the template saved at {:?}, use --refresh-template to download it again",
+                    cached
+                );
+                // it may be of another onboard version, or have been edited since it was checked
+                manifest::check_template_schema(&cached).with_context(|| {
+                    format!("the saved template {:?} can't be used, pass --refresh-template to download it again", cached)
+                })?;
+            } else {
+                let url = template_web_url(url, self.opts.skip_template_port_rewrite)?;
+                let path = save_template(&url.join("account.json")?, home_path);
+                if let Err(e) = manifest::check_template_schema(&path) {
+                    // don't reuse an incompatible template on the next run
+                    fs::remove_file(&path).ok();
+                    return Err(e);
+                }
+                // get autopay
+                status_ok!("\nTemplate saved", "\n...........................\n");
+            }
         }
 
-        // Use any autopay instructions
-        // TODO: simplify signature
-        let (autopay_batch, autopay_signed) = get_autopay_batch(
-            &self.template_url,
-            &self.autopay_file,
-            home_path,
-            &app_config,
-            &wallet,
-            entry_args.swarm_path.as_ref().is_some(),
-            *&self.genesis_ceremony,
-        );
-        status_ok!(
-            "\nAutopay transactions signed",
-            "\n...........................\n"
-        );
+        let base_epoch = self.opts.base_epoch.or_else(|| {
+            upstream_peer
+                .as_ref()
+                .and_then(|u| offer_chain_epoch(u, app_config.chain_info.base_epoch))
+        });
 
-        // Initialize Validator Keys
-        init_cmd::initialize_validator(&wallet, &app_config, base_waypoint, *&self.genesis_ceremony).expect("could not initialize validator key_store.json");
-        status_ok!("\nKey file written", "\n...........................\n");
+        Ok(WizardConfig {
+            app_config,
+            template_url,
+            upstream_peer,
+            base_epoch,
+            lock: Some(lock),
+        })
+    }
 
-        if !self.genesis_ceremony {
-            // fetching the genesis files from genesis-archive, will override the path for prebuilt genesis.
-            let mut prebuilt_genesis_path = self.prebuilt_genesis.clone();
-            if self.fetch_git_genesis {
-                files_cmd::get_files(home_path.clone(), &self.github_org, &self.repo);
+    /// Signs the autopay instructions, or reuses the ones of the source manifest.
+    pub fn sign_autopay(
+        &self,
+        token: &CancelToken,
+        keys: &WizardKeys,
+        cfg: &WizardConfig,
+    ) -> Result<(Option<Vec<PayInstruction>>, Option<Vec<SignedTransaction>>), Error> {
+        token.check()?;
+        let home_path = &cfg.app_config.workspace.node_home;
+        let (autopay_batch, autopay_signed) = match (&keys.wallet, &keys.source_manifest) {
+            (Some(_), Some(m)) => {
+                println!("Reusing the autopay of the manifest, without signing again");
+                (m.autopay_instructions.clone(), m.autopay_signed.clone())
+            }
+            (Some(w), None) => {
+                let opts = AutopayOptions {
+                    template: cfg.template_url.clone(),
+                    file_path: self.opts.autopay_file.clone(),
+                    default_name: self.opts.autopay_default_name.clone(),
+                    seq_start: self.opts.autopay_seq_start,
+                    upstream: cfg.upstream_peer.clone(),
+                    account_index: keys.account_index,
+                    base_epoch: cfg.base_epoch,
+                    start_offset: self.opts.autopay_start_offset,
+                    review: !self.opts.no_autopay_review,
+                    percent_cap: if self.opts.force {
+                        None
+                    } else {
+                        Some(self.opts.autopay_percent_cap.unwrap_or(DEFAULT_AUTOPAY_PERCENT_CAP))
+                    },
+                    continue_on_error: self.opts.continue_on_autopay_error,
+                    validate_destinations: self.opts.validate_autopay_destinations,
+                };
+                let batch = get_autopay_batch(
+                    home_path,
+                    &cfg.app_config,
+                    w,
+                    self.swarm,
+                    self.builds_genesis(),
+                    &opts,
+                )?;
                 status_ok!(
-                    "\nDownloaded genesis files",
+                    "\nAutopay transactions signed",
                     "\n...........................\n"
                 );
+                batch
+            }
+            (None, _) => (None, None),
+        };
 
-                prebuilt_genesis_path = Some(home_path.join("genesis.blob"));
-            } else if self.ci {
-                fs::copy(
-                    get_test_genesis_blob().as_os_str(),
-                    home_path.join("genesis.blob"),
-                )
-                .unwrap();
-                prebuilt_genesis_path = Some(home_path.join("genesis.blob"));
-                status_ok!(
-                    "\nUsing test genesis.blob",
-                    "\n...........................\n"
-                );
+        if let Some(signed) = &autopay_signed {
+            let hashes = autopay_tx_hashes(signed);
+            signed.iter().zip(hashes.iter()).for_each(|(tx, h)| {
+                println!("autopay tx sequence number {}, hash: {}", tx.sequence_number(), h);
+            });
+            print_gas_bound(autopay_batch.as_deref().unwrap_or(&[]), signed);
+            if self.opts.save_tx_hashes {
+                let hex: Vec<String> = hashes.iter().map(|h| h.to_hex()).collect();
+                let hash_path = home_path.join("autopay_tx_hashes.json");
+                fs::write(&hash_path, serde_json::to_string_pretty(&hex)?)
+                    .context("could not write autopay_tx_hashes.json")?;
+                println!("autopay tx hashes saved to: {:?}", hash_path);
             }
+        }
+        Ok((autopay_batch, autopay_signed))
+    }
 
-            let home_dir = app_config.workspace.node_home.to_owned();
-            // 0L convention is for the namespace of the operator to be appended by '-oper'
-            let namespace = app_config.profile.auth_key.clone() + "-oper";
-
-            // TODO: use node_config to get the seed peers and then write upstream_node vec in 0L.toml from that.
-            ol_node_files::write_node_config_files(
-                home_dir.clone(),
-                self.chain_id.unwrap_or(1),
-                &self.github_org.clone().unwrap_or("OLSF".to_string()),
-                &self
-                    .repo
-                    .clone()
-                    .unwrap_or("experimental-genesis".to_string()),
-                &namespace,
-                &prebuilt_genesis_path,
-                &false,
-                base_waypoint,
-                &None,
+    /// Writes the key store, unless it is being reused.
+    pub fn write_keys(&self, token: &CancelToken, keys: &WizardKeys, cfg: &WizardConfig) -> Result<(), Error> {
+        token.check()?;
+        if let Some(w) = &keys.wallet {
+            init_cmd::initialize_validator_keys(
+                KeyScheme::new_with_index(w, keys.account_index),
+                &cfg.app_config,
+                cfg.app_config.chain_info.base_waypoint,
+                self.builds_genesis(),
+                Some(&|step: init_cmd::InitStep, namespace: &str| {
+                    println!("key_store.json: writing {:?}, namespace {}", step, namespace)
+                }),
             )
-            .unwrap();
+            .context("could not initialize validator key_store.json")?;
+            let home_path = &cfg.app_config.workspace.node_home;
+            if self.namespace_suffix() != key_store::DEFAULT_NAMESPACE_SUFFIX {
+                // the key store init writes the keys with the 0L convention
+                key_store::rename_operator_namespace(
+                    home_path,
+                    cfg.app_config.profile.account,
+                    self.namespace_suffix(),
+                )?;
+            }
+            self.set_file_modes(&[
+                (home_path.join(key_store::KEY_STORE_FILE), true),
+                (home_path.join(key_store::CHECKSUM_FILE), false),
+            ])?;
+            status_ok!("\nKey file written", "\n...........................\n");
+        } else {
+            status_ok!("\nReusing key file", "\n...........................\n");
+        }
+        Ok(())
+    }
+
+    /// Genesis is made by this run, so there is no chain to query yet
+    fn builds_genesis(&self) -> bool {
+        self.opts.genesis_ceremony || self.opts.force_rebuild_genesis.is_some()
+    }
 
-            status_ok!("\nNode config written", "\n...........................\n");
+    /// Gets the genesis files and writes the node configs, except at a genesis ceremony.
+    pub fn write_node_files(&self, token: &CancelToken, cfg: &WizardConfig) -> Result<(), Error> {
+        token.check()?;
+        if self.opts.genesis_ceremony {
+            return Ok(());
         }
+        let app_config = &cfg.app_config;
+        let home_path = &app_config.workspace.node_home;
+        let chain_id = self.opts.chain_id.unwrap_or(1);
+        // fetching the genesis files from genesis-archive, will override the path for prebuilt genesis.
+        let mut prebuilt_genesis_path = self.opts.prebuilt_genesis.clone();
+        let mut waypoint = app_config.chain_info.base_waypoint;
+        if let Some(contributions) = &self.opts.force_rebuild_genesis {
+            if self.fetches_genesis() || self.opts.prebuilt_genesis.is_some() || self.opts.ci {
+                bail!("--force-rebuild-genesis can't be used with --fetch-git-genesis, --genesis-source, --prebuilt-genesis, or --ci");
+            }
+            let namespace = app_config.profile.auth_key.clone() + self.namespace_suffix();
+            let built = build_genesis::build_genesis(home_path, contributions, chain_id, &namespace)
+                .context("could not rebuild genesis from the contributions")?;
+            status_ok!("\nGenesis built", &format!("waypoint {}", built));
+            prebuilt_genesis_path = Some(home_path.join("genesis.blob"));
+            waypoint = Some(built);
+        } else if self.fetches_genesis() {
+            let fetched = files_cmd::genesis_source(&self.opts.genesis_source, &self.opts.github_org, &self.opts.repo)
+                .and_then(|source| files_cmd::get_files_from(home_path.clone(), source, token));
+            // don't fall back to the bundled genesis when the fetch stopped on a Ctrl-C
+            token.check()?;
+            match fetched {
+                Ok(_) => {
+                    status_ok!(
+                        "\nDownloaded genesis files",
+                        "\n...........................\n"
+                    );
+                }
+                Err(e) if self.opts.allow_bundled_genesis => {
+                    println!("WARN: could not fetch genesis files, falling back to bundled genesis. Message: {:?}", e);
+                    files_cmd::write_bundled_genesis(home_path, chain_id)
+                        .context("could not use bundled genesis")?;
+                    status_ok!(
+                        "\nUsing bundled genesis files",
+                        "\n...........................\n"
+                    );
+                }
+                Err(e) => {
+                    return Err(e.context("could not fetch genesis files, use --allow-bundled-genesis as a last resort"));
+                }
+            }
 
-        if !self.skip_mining {
-            // Mine Block
-            miner::block::write_genesis(&app_config);
+            prebuilt_genesis_path = Some(home_path.join("genesis.blob"));
+        } else if self.opts.ci {
+            fs::copy(
+                get_test_genesis_blob().as_os_str(),
+                home_path.join("genesis.blob"),
+            )?;
+            prebuilt_genesis_path = Some(home_path.join("genesis.blob"));
             status_ok!(
-                "\nGenesis proof complete",
+                "\nUsing test genesis.blob",
                 "\n...........................\n"
             );
         }
 
-        // Write account manifest
-        write_account_json(
-            &self.output_path,
-            wallet,
-            Some(app_config.clone()),
-            autopay_batch,
-            autopay_signed,
+        if self.opts.verify_genesis {
+            match &prebuilt_genesis_path {
+                Some(path) => {
+                    let computed = read_genesis::expected_waypoint(path)
+                        .and_then(|expected| read_genesis::verify_genesis(path, expected))
+                        .context("genesis verification failed")?;
+                    status_ok!("\nGenesis verified", &format!("waypoint {}", computed));
+                }
+                None => println!("WARN: --verify-genesis needs a genesis.blob, skipping"),
+            }
+        }
+
+        token.check()?;
+        let home_dir = app_config.workspace.node_home.to_owned();
+        // 0L convention is for the namespace of the operator to be appended by '-oper'
+        let namespace = app_config.profile.auth_key.clone() + self.namespace_suffix();
+
+        // TODO: use node_config to get the seed peers and then write upstream_node vec in 0L.toml from that.
+        ol_node_files::write_node_config_files(
+            home_dir.clone(),
+            chain_id,
+            &self.opts.github_org.clone().unwrap_or("OLSF".to_string()),
+            &self
+                .opts
+                .repo
+                .clone()
+                .unwrap_or("experimental-genesis".to_string()),
+            &namespace,
+            &prebuilt_genesis_path,
+            &false,
+            waypoint,
+            &None,
+        )
+        .map_err(|e| Error::msg(format!("could not write node config files: {:?}", e)))?;
+        self.set_file_modes(&[
+            (home_dir.join("validator.node.yaml"), false),
+            (home_dir.join("fullnode.node.yaml"), false),
+            (home_dir.join("genesis.blob"), false),
+            (home_dir.join("genesis_waypoint"), false),
+        ])?;
+
+        status_ok!("\nNode config written", "\n...........................\n");
+        if let Some(out) = &self.opts.genesis_waypoint_out {
+            match waypoint {
+                Some(w) => {
+                    fs::write(out, w.to_string())
+                        .with_context(|| format!("could not write the waypoint to {:?}", out))?;
+                    println!("Waypoint {} written to {:?}", w, out);
+                }
+                None => println!("WARN: no waypoint was set, not writing {:?}", out),
+            }
+        }
+        Ok(())
+    }
+
+    /// Mines block zero, or reuses the one of the source manifest. A failed proof is left
+    /// pending, to be imported later, rather than losing the configs written so far.
+    /// Returns the metrics of a block mined in this run.
+    pub fn mine_block_zero(
+        &self,
+        token: &CancelToken,
+        keys: &WizardKeys,
+        cfg: &WizardConfig,
+    ) -> Result<Option<manifest::MiningMetrics>, Error> {
+        token.check()?;
+        let app_config = &cfg.app_config;
+        let proof_reused = match &keys.source_manifest {
+            Some(m) if !m.proof_pending => {
+                write_block_zero(app_config, &m.block_zero)?;
+                status_ok!("\nReusing the block zero of the manifest", "\n...........................\n");
+                true
+            }
+            _ => false,
+        };
+
+        let mut metrics = None;
+        if !self.opts.skip_mining && !self.opts.reuse_keys && !proof_reused {
+            let chain_id = self.opts.chain_id.unwrap_or(1);
+            if let Err(e) = precheck::check_difficulty(chain_id, miner::delay::delay_difficulty()) {
+                if self.opts.allow_difficulty_mismatch || self.opts.ci || self.swarm {
+                    println!("WARN: {}, mining anyway", e);
+                } else {
+                    return Err(e.context("not mining, pass --allow-difficulty-mismatch to mine anyway"));
+                }
+            }
+            // Mine Block, which is removed if cancelled before it's complete
+            let block_path = app_config.get_block_dir().join("block_0.json");
+            token.check()?;
+            cancel::track(block_path.clone());
+            let started = Instant::now();
+            let mined = miner::block::write_genesis(app_config);
+            let elapsed = started.elapsed();
+            // the VDF can't be interrupted, a Ctrl-C while mining stops the wizard once it's done,
+            // and the block is still tracked, so it's removed with the other partial artifacts
+            token.check()?;
+            cancel::untrack(&block_path);
+            match mined {
+                Ok((block, difficulty)) => {
+                    status_ok!(
+                        "\nGenesis proof complete",
+                        "\n...........................\n"
+                    );
+                    // written with the other statuses, so it's kept in the --status-log
+                    let mined = manifest::MiningMetrics::new(elapsed, block.elapsed_secs, difficulty);
+                    status_info!("Mining metrics", &mined.summary());
+                    if self.opts.report_hardware {
+                        status_info!("Hardware", &precheck::Hardware::detect());
+                    }
+                    metrics = Some(mined);
+                }
+                Err(e) => {
+                    fs::remove_file(&block_path).ok();
+                    println!(
+                        "WARN: mining block zero failed, the manifest will be marked as proof pending. Message: {:?}",
+                        e
+                    );
+                }
+            }
+        }
+        self.set_file_modes(&[(app_config.get_block_dir().join("block_0.json"), false)])?;
+        Ok(metrics)
+    }
+
+    /// Writes account.json, and the BCS copy, report, and systemd unit which are asked for.
+    /// The proof is checked against the difficulty of block zero if it was mined in this run,
+    /// and its metrics are written to mining.json.
+    pub fn write_manifest(
+        &self,
+        token: &CancelToken,
+        keys: &WizardKeys,
+        cfg: &WizardConfig,
+        autopay_batch: Option<Vec<PayInstruction>>,
+        autopay_signed: Option<Vec<SignedTransaction>>,
+        metrics: Option<manifest::MiningMetrics>,
+    ) -> Result<(), Error> {
+        token.check()?;
+        let app_config = &cfg.app_config;
+        let home_path = &app_config.workspace.node_home;
+        // Write account manifest, which needs the mnemonic
+        let operator = match &self.opts.operator_file {
+            Some(path) if self.opts.owner_only => {
+                let operator = OperatorConfigs::read_file(path)
+                    .with_context(|| format!("could not read operator file {:?}", path))?;
+                if operator.owner_address.parse::<AccountAddress>().ok() != Some(keys.account) {
+                    bail!(
+                        "{:?} is for the owner {}, but the mnemonic is for {}",
+                        path,
+                        operator.owner_address,
+                        keys.account
+                    );
+                }
+                println!("Delegating to operator {}", operator.op_address);
+                Some(operator)
+            }
+            _ if self.opts.owner_only => bail!("--owner-only needs the operator.json of the operator, pass --operator-file"),
+            _ => None,
+        };
+        match &keys.wallet {
+            Some(w) => {
+                write_account_json(
+                    &self.opts.output_path,
+                    KeyScheme::new_with_index(w, keys.account_index),
+                    Some(app_config.clone()),
+                    autopay_batch,
+                    autopay_signed,
+                    self.opts.manifest_include_private,
+                    operator,
+                )?;
+                status_ok!(
+                    "\nAccount manifest written",
+                    "\n...........................\n"
+                );
+                // verifiers reject a proof whose recorded parameters drifted from the mined ones
+                let manifest_dir = self.opts.output_path.clone().unwrap_or(home_path.clone());
+                let manifest = manifest::read_manifest(&manifest_dir.join("account.json"))?;
+                if let Some(m) = &metrics {
+                    if !manifest.proof_pending {
+                        manifest::check_proof_difficulty(&manifest, &app_config.get_block_dir(), m.iterations)
+                            .context("the proof difficulties don't match, not using the manifest")?;
+                    }
+                    m.write(&manifest_dir)?;
+                }
+            }
+            None => println!(
+                "Keeping the existing account.json, run `onboard fix --account` with the mnemonic to regenerate it."
+            ),
+        }
+
+        let manifest_dir = self.opts.output_path.clone().unwrap_or(home_path.clone());
+        let manifest_path = manifest_dir.join("account.json");
+        if self.opts.bcs_manifest {
+            ValConfigs::get_init_data(&manifest_path)
+                .map_err(Error::from)
+                .and_then(|m| m.create_manifest_bcs(manifest_dir.clone()))
+                .unwrap_or_else(|e| {
+                    println!("WARN: could not write account.bcs. Message: {:?}", e);
+                });
+        }
+        // the manifest only has key material when it includes the private keys
+        self.set_file_modes(&[
+            (manifest_path.clone(), self.opts.manifest_include_private),
+            (manifest_dir.join("account.bcs"), false),
+        ])?;
+
+        if let Some(url) = &self.opts.report_to {
+            match manifest::report_manifest(url, &manifest_path) {
+                Ok((status, body)) if status.is_success() => {
+                    status_ok!("\nManifest reported", &format!("to {}, response: {} {}", url, status, body));
+                }
+                Ok((status, body)) => {
+                    println!("WARN: {} rejected the manifest, response: {} {}", url, status, body);
+                }
+                Err(e) => {
+                    println!("WARN: could not report the manifest to {}. Message: {:?}", url, e);
+                }
+            }
+        }
+
+        if self.opts.write_systemd_unit {
+            let node_bin = self
+                .opts
+                .node_bin
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(systemd::DEFAULT_NODE_BIN));
+            let out = self.opts.systemd_unit_path.clone().unwrap_or_else(|| home_path.clone());
+            match systemd::write_node_unit(home_path, &node_bin, &out) {
+                Ok(path) => {
+                    status_ok!(
+                        "\nSystemd unit written",
+                        &format!(
+                            "to {:?}, install it with `sudo cp {} /etc/systemd/system/ && sudo systemctl enable --now {}`",
+                            &path,
+                            path.display(),
+                            systemd::NODE_UNIT_FILE
+                        )
+                    );
+                }
+                Err(e) => println!("WARN: could not write the systemd unit. Message: {:?}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// All the phases of the wizard, in order
+    pub fn run_phases(&self, token: &CancelToken) -> Result<WizardConfig, Error> {
+        if self.opts.operator_only && self.opts.owner_only {
+            bail!("--operator-only and --owner-only are run by different people, pass one of them");
+        }
+        if let Some(mode) = &self.opts.file_mode {
+            permissions::parse_file_mode(mode)?;
+        }
+        self.check_url_schemes()?;
+        if self.opts.contact.as_ref().map_or(false, |c| c.trim().is_empty()) {
+            bail!("--contact is empty, pass an email, matrix handle, or URL to reach the operator at");
+        }
+        if let Some(suffix) = &self.opts.namespace_suffix {
+            key_store::check_namespace_suffix(suffix)?;
+        }
+        let keys = self.derive_keys(token)?;
+        if !self.opts.only_phase.is_empty() {
+            return self.run_only_phases(token, keys);
+        }
+        // without a home set, it is asked for when writing the config, so only the files after it are checked
+        if let Some(home) = &keys.config_home {
+            self.confirm_overwrites(home, true)?;
+        }
+        let cfg = self.write_config(token, &keys)?;
+        if keys.config_home.is_none() {
+            self.confirm_overwrites(&cfg.app_config.workspace.node_home, false)?;
+        }
+        if self.opts.operator_only {
+            // the owner signs autopay and mines block zero in the --owner-only run
+            self.write_keys(token, &keys, &cfg)?;
+            self.write_node_files(token, &cfg)?;
+            self.write_operator_file(token, &keys, &cfg)?;
+        } else {
+            let (autopay_batch, autopay_signed) = self.sign_autopay(token, &keys, &cfg)?;
+            if !self.opts.owner_only {
+                self.write_keys(token, &keys, &cfg)?;
+                self.write_node_files(token, &cfg)?;
+            }
+            let metrics = self.mine_block_zero(token, &keys, &cfg)?;
+            self.write_manifest(token, &keys, &cfg, autopay_batch, autopay_signed, metrics)?;
+        }
+        self.write_bundle(token, &cfg)?;
+        Ok(cfg)
+    }
+
+    /// Suffix of the operator namespace, -oper unless --namespace-suffix is set
+    fn namespace_suffix(&self) -> &str {
+        self.opts.namespace_suffix
+            .as_deref()
+            .unwrap_or(key_store::DEFAULT_NAMESPACE_SUFFIX)
+    }
+
+    /// With --only-phase, whether the phase was named
+    fn runs(&self, phase: &str) -> bool {
+        self.opts.only_phase.iter().any(|p| p == phase)
+    }
+
+    /// Checks the phases named with --only-phase exist, and that the files of the phases
+    /// they need, which are not run, are in the home already.
+    fn check_only_phases(&self, keys: &WizardKeys) -> Result<(), Error> {
+        if let Some(unknown) = self.opts.only_phase.iter().find(|p| !PHASES.contains(&p.as_str())) {
+            bail!("no phase {}, the phases are: {}", unknown, PHASES.join(", "));
+        }
+        let home_path = match &keys.config_home {
+            Some(h) => h,
+            None if self.runs("write-config") => return Ok(()),
+            None => bail!("--only-phase without write-config needs the node home, pass --home-path"),
+        };
+        let needs = |phase: &str, prerequisite: &str, file: PathBuf| -> Result<(), Error> {
+            if self.runs(phase) && !self.runs(prerequisite) && !file.exists() {
+                bail!("{} needs {:?}, from {}, run it too", phase, file, prerequisite);
+            }
+            Ok(())
+        };
+        let block_dir = if home_path.join(CONFIG_FILE).exists() {
+            home::try_read_configs(&Some(home_path.clone()))?.get_block_dir()
+        } else {
+            home_path.join("blocks")
+        };
+        // every phase after write-config reads 0L.toml
+        for phase in PHASES.iter().skip(2) {
+            needs(phase, "write-config", home_path.join(CONFIG_FILE))?;
+        }
+        needs("write-node-files", "write-keys", home_path.join(key_store::KEY_STORE_FILE))?;
+        needs("write-manifest", "mine-block-zero", block_dir.join("block_0.json"))?;
+        if self.runs("write-manifest") && keys.wallet.is_none() {
+            bail!("write-manifest needs the mnemonic, it can't be run with --reuse-keys");
+        }
+        if self.runs("write-manifest")
+            && !self.runs("sign-autopay")
+            && (self.opts.autopay_file.is_some() || self.opts.template_url.is_some())
+        {
+            bail!("write-manifest with autopay instructions needs sign-autopay, run it too");
+        }
+        if self.runs("write-operator-file") && !self.opts.operator_only {
+            bail!("write-operator-file is only run with --operator-only");
+        }
+        Ok(())
+    }
+
+    /// The configs of the home, for the phases after write-config when it isn't run
+    fn existing_config(&self, home_path: &Path) -> Result<WizardConfig, Error> {
+        let app_config = home::try_read_configs(&Some(home_path.to_owned()))?;
+        Ok(WizardConfig {
+            template_url: self.opts.template_url.clone(),
+            upstream_peer: app_config
+                .profile
+                .upstream_nodes
+                .as_ref()
+                .and_then(|nodes| nodes.first().cloned()),
+            base_epoch: app_config.chain_info.base_epoch,
+            app_config,
+            lock: None,
+        })
+    }
+
+    /// Runs the phases named with --only-phase, in the order of PHASES
+    fn run_only_phases(&self, token: &CancelToken, keys: WizardKeys) -> Result<WizardConfig, Error> {
+        self.check_only_phases(&keys)?;
+        let cfg = if self.runs("write-config") {
+            if let Some(home) = &keys.config_home {
+                self.confirm_overwrites(home, true)?;
+            }
+            self.write_config(token, &keys)?
+        } else {
+            // checked above, the home is known without write-config
+            let home = keys.config_home.as_ref().unwrap();
+            precheck::check_no_running_node(home, self.opts.force)?;
+            self.existing_config(home)?
+        };
+        let (autopay_batch, autopay_signed) = if self.runs("sign-autopay") {
+            self.sign_autopay(token, &keys, &cfg)?
+        } else {
+            (None, None)
+        };
+        if self.runs("write-keys") {
+            self.write_keys(token, &keys, &cfg)?;
+        }
+        if self.runs("write-node-files") {
+            self.write_node_files(token, &cfg)?;
+        }
+        if self.runs("write-operator-file") {
+            self.write_operator_file(token, &keys, &cfg)?;
+        }
+        let mut metrics = None;
+        if self.runs("mine-block-zero") {
+            metrics = self.mine_block_zero(token, &keys, &cfg)?;
+        }
+        if self.runs("write-manifest") {
+            self.write_manifest(token, &keys, &cfg, autopay_batch, autopay_signed, metrics)?;
+        }
+        if self.runs("write-bundle") {
+            self.write_bundle(token, &cfg)?;
+        }
+        Ok(cfg)
+    }
+
+    /// With --bundle-out, writes the files of the run to a tarball, laid out as in the node home
+    pub fn write_bundle(&self, token: &CancelToken, cfg: &WizardConfig) -> Result<(), Error> {
+        token.check()?;
+        let out = match &self.opts.bundle_out {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let app_config = &cfg.app_config;
+        let home_path = &app_config.workspace.node_home;
+        let manifest_dir = self.opts.output_path.clone().unwrap_or_else(|| home_path.clone());
+        let manifest_file = if self.opts.operator_only { OPERATOR_FILE } else { "account.json" };
+        let entry = |path: PathBuf, name: &str, private: bool| TarballEntry {
+            path,
+            name: name.to_owned(),
+            private,
+        };
+        let block_name = format!("{}/block_0.json", app_config.workspace.block_dir);
+        let entries = vec![
+            entry(home_path.join(CONFIG_FILE), CONFIG_FILE, false),
+            entry(home_path.join(key_store::KEY_STORE_FILE), key_store::KEY_STORE_FILE, true),
+            entry(home_path.join("validator.node.yaml"), "validator.node.yaml", false),
+            entry(home_path.join("fullnode.node.yaml"), "fullnode.node.yaml", false),
+            entry(app_config.get_block_dir().join("block_0.json"), &block_name, false),
+            entry(manifest_dir.join(manifest_file), manifest_file, false),
+            entry(manifest_dir.join(manifest::MINING_FILE), manifest::MINING_FILE, false),
+        ];
+        let missing = tarball::write_tarball(out, &entries)
+            .with_context(|| format!("could not write the bundle {:?}", out))?;
+        if !missing.is_empty() {
+            println!("WARN: not in the bundle, since this run didn't write them: {}", missing.join(", "));
+        }
+        status_ok!("\nBundle written", &format!("{:?}", out));
+        println!(
+            "WARN: {:?} has the private keys of the node in key_store.json. Move it to the node over a secure channel, and delete it once extracted in the node home.",
+            out
         );
-        status_ok!(
-            "\nAccount manifest written",
-            "\n...........................\n"
+        Ok(())
+    }
+
+    /// Interactive runs verify by default, ci, swarms and partial runs only with --verify-after-write
+    fn verifies_after_write(&self) -> bool {
+        self.opts.verify_after_write || !(self.opts.ci || *IS_TEST || self.swarm || !self.opts.only_phase.is_empty())
+    }
+
+    /// Runs the checks of the files the run wrote against each other: the manifest, the key
+    /// store checksum, the consensus key of both, and the waypoints. Prints a report of all
+    /// of them, and errors if any failed.
+    pub fn verify_after_write(&self, cfg: &WizardConfig) -> Result<(), Error> {
+        let app_config = &cfg.app_config;
+        let home_path = &app_config.workspace.node_home;
+        let manifest_dir = self.opts.output_path.clone().unwrap_or_else(|| home_path.clone());
+        let manifest_path = manifest_dir.join("account.json");
+        let mut checks = vec![];
+
+        let manifest = if self.opts.operator_only || self.opts.reuse_keys {
+            None
+        } else {
+            match manifest::validate_manifest(&manifest_path) {
+                // the proof is mined later with --skip-mining
+                Ok(c) => checks.extend(
+                    c.into_iter()
+                        .filter(|c| !(self.opts.skip_mining && c.name == "block zero proof")),
+                ),
+                Err(e) => checks.push(ManifestCheck { name: "manifest readable", result: Err(e) }),
+            }
+            manifest::read_manifest(&manifest_path).ok()
+        };
+
+        if !self.opts.owner_only {
+            checks.push(ManifestCheck {
+                name: "key store checksum",
+                result: key_store::verify_checksum(home_path).and_then(|s| match s {
+                    key_store::ChecksumStatus::Verified => Ok(()),
+                    key_store::ChecksumStatus::Missing => Err(Error::msg("no checksum was written")),
+                }),
+            });
+            match key_store::read_key_store_with_suffix(home_path, self.namespace_suffix()) {
+                Ok(ks) => {
+                    if let Some(m) = &manifest {
+                        checks.push(ManifestCheck {
+                            name: "consensus key of the manifest and key store",
+                            result: ks.public_key(CONSENSUS_KEY).and_then(|k| {
+                                if k.to_bytes().to_vec() == m.op_consensus_pubkey {
+                                    Ok(())
+                                } else {
+                                    Err(Error::msg("the manifest has another consensus key than the key store"))
+                                }
+                            }),
+                        });
+                    }
+                    checks.push(ManifestCheck {
+                        name: "waypoint of the key store and configs",
+                        result: check_written_waypoint(&ks, app_config),
+                    });
+                }
+                Err(e) => checks.push(ManifestCheck { name: "key store readable", result: Err(e) }),
+            }
+        }
+
+        println!("\nVerification of the files written");
+        checks.iter().for_each(|c| match &c.result {
+            Ok(_) => println!("PASS: {}", c.name),
+            Err(e) => println!("FAIL: {}, {}", c.name, e),
+        });
+        let failed = checks.iter().filter(|c| c.result.is_err()).count();
+        if failed > 0 {
+            bail!("{} of {} checks failed on the files written", failed, checks.len());
+        }
+        Ok(())
+    }
+
+    /// With --strict-url-scheme, errors on the first URL flag which isn't https
+    fn check_url_schemes(&self) -> Result<(), Error> {
+        if !self.opts.strict_url_scheme {
+            return Ok(());
+        }
+        let mut urls = vec![];
+        let flags = [
+            ("--template-url", &self.opts.template_url),
+            ("--upstream-peer", &self.opts.upstream_peer),
+            ("--report-to", &self.opts.report_to),
+            ("--ip-service", &self.opts.ip_service),
+        ];
+        for (flag, url) in flags.iter() {
+            if let Some(u) = url {
+                urls.push((*flag, u.to_string()));
+            }
+        }
+        // github:// is fetched from the https raw files of the repo
+        if let Some(source) = self.opts.genesis_source.as_ref().filter(|s| !s.starts_with("github://")) {
+            urls.push(("--genesis-source", source.clone()));
+        }
+        // the chain info can be a file, which has no scheme to check
+        if let Some(info) = self.opts.chain_info.as_ref().filter(|c| Url::parse(c).is_ok()) {
+            urls.push(("--chain-info", info.clone()));
+        }
+        for (flag, url) in urls {
+            check_strict_scheme(flag, &url)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the modes of the files a phase wrote, the --file-mode for all of them if it's set.
+    /// They are also registered to be removed if the run is cancelled.
+    fn set_file_modes(&self, files: &[(PathBuf, bool)]) -> Result<(), Error> {
+        files
+            .iter()
+            .filter(|(p, _)| p.exists())
+            .for_each(|(p, _)| cancel::written(p.clone()));
+        let mode = match &self.opts.file_mode {
+            Some(m) => Some(permissions::parse_file_mode(m)?),
+            None => None,
+        };
+        permissions::set_modes(files, mode)
+    }
+
+    /// --genesis-source fetches the genesis files, like --fetch-git-genesis does from github
+    fn fetches_genesis(&self) -> bool {
+        self.opts.fetch_git_genesis || self.opts.genesis_source.is_some()
+    }
+
+    /// Stops if a node runs against the home. With --confirm-destructive, asks before overwriting
+    /// the files this run writes which are already in the home. Without a terminal to ask on,
+    /// continues only with --force.
+    fn confirm_overwrites(&self, home_path: &Path, include_config: bool) -> Result<(), Error> {
+        precheck::check_no_running_node(home_path, self.opts.force)?;
+        if !self.opts.confirm_destructive || self.opts.force {
+            return Ok(());
+        }
+        let manifest_dir = self.opts.output_path.clone().unwrap_or_else(|| home_path.to_owned());
+        let mut files = vec![];
+        if include_config {
+            files.push(home_path.join(CONFIG_FILE));
+        }
+        if !self.opts.reuse_keys && !self.opts.owner_only {
+            files.push(home_path.join(key_store::KEY_STORE_FILE));
+        }
+        if self.opts.operator_only {
+            files.push(manifest_dir.join(OPERATOR_FILE));
+        } else if !self.opts.reuse_keys {
+            files.push(manifest_dir.join("account.json"));
+        }
+        let existing: Vec<PathBuf> = files.into_iter().filter(|f| f.exists()).collect();
+        if existing.is_empty() {
+            return Ok(());
+        }
+
+        println!("These files of a previous run will be overwritten:");
+        existing.iter().for_each(|f| println!("  {:?}", f));
+        match Confirm::new().with_prompt("Overwrite them?").interact() {
+            Ok(true) => Ok(()),
+            Ok(false) => bail!("not overwriting the files of the previous run"),
+            Err(_) => bail!("files of a previous run would be overwritten, pass --force to overwrite them without asking"),
+        }
+    }
+
+    /// Writes operator.json, the public operator fields the owner needs for a delegated manifest.
+    pub fn write_operator_file(&self, token: &CancelToken, keys: &WizardKeys, cfg: &WizardConfig) -> Result<(), Error> {
+        token.check()?;
+        let wallet = keys
+            .wallet
+            .as_ref()
+            .ok_or_else(|| Error::msg("operator.json needs the mnemonic of the operator"))?;
+        let operator = OperatorConfigs::new(
+            &KeyScheme::new_with_index(wallet, keys.account_index),
+            cfg.app_config.profile.ip.to_string(),
+            keys.account,
+        );
+        let dir = self
+            .opts
+            .output_path
+            .clone()
+            .unwrap_or_else(|| cfg.app_config.workspace.node_home.clone());
+        let path = operator.create_file(dir).context("could not write operator.json")?;
+        self.set_file_modes(&[(path.clone(), false)])?;
+        status_ok!("\nOperator file written", &format!("{:?}", path));
+        println!(
+            "Send it to the owner of {}, who runs `onboard val --owner-only --operator-file operator.json`",
+            keys.account
         );
+        Ok(())
+    }
+}
+
+/// Outcome of a wizard run, for the tools which embed it
+#[derive(Debug)]
+pub struct WizardOutput {
+    /// the 0L.toml written
+    pub app_config: AppCfg,
+    /// the account onboarded
+    pub account: AccountAddress,
+    /// upstream peer the run queried the chain at
+    pub upstream_peer: Option<Url>,
+    /// account.json, or operator.json of an --operator-only run, if the run wrote it
+    pub manifest: Option<PathBuf>,
+    /// block zero is still to be mined, with --skip-mining
+    pub block_zero_pending: bool,
+    /// the tarball written with --bundle-out
+    pub bundle: Option<PathBuf>,
+    /// the files written were checked against each other
+    pub verified: bool,
+}
+
+/// Runs the phases of the wizard with the options, without the abscissa harness. Prompts
+/// for what the options leave out, as `onboard val` does. swarm is for a run against a
+/// local swarm. The phases stop at their next check once the token is cancelled, the
+/// caller decides what cancels it.
+pub fn run_wizard(opts: &WizardOptions, swarm: bool, token: &CancelToken) -> Result<WizardOutput, Error> {
+    if let Some(path) = &opts.status_log {
+        status::set_log(path).context("could not use the status log")?;
+    }
+    let wizard = Wizard { opts, swarm };
+    let cfg = wizard.run_phases(token)?;
+    let verified = wizard.verifies_after_write();
+    if verified {
+        wizard.verify_after_write(&cfg)?;
+    }
+
+    let app_config = cfg.app_config;
+    let home_path = &app_config.workspace.node_home;
+    let manifest_dir = opts.output_path.clone().unwrap_or_else(|| home_path.clone());
+    let manifest_file = if opts.operator_only { OPERATOR_FILE } else { "account.json" };
+    let manifest = Some(manifest_dir.join(manifest_file)).filter(|p| p.exists());
+    Ok(WizardOutput {
+        account: app_config.profile.account,
+        upstream_peer: cfg.upstream_peer,
+        manifest,
+        block_zero_pending: !app_config.get_block_dir().join("block_0.json").exists(),
+        bundle: opts.bundle_out.clone(),
+        verified,
+        app_config,
+    })
+}
+
+impl Runnable for ValWizardCmd {
+    fn run(&self) {
+        // Note. `onboard` command DOES NOT READ CONFIGS FROM 0L.toml
+
+        if let Some(path) = &self.opts.status_log {
+            status::set_log(path).unwrap_or_else(|e| {
+                println!("ERROR: could not use the status log, exiting. Message: {:?}", e);
+                exit(1)
+            });
+        }
+
+        if self.opts.list_phases {
+            PHASES.iter().for_each(|p| println!("{}", p));
+            return;
+        }
+
+        if self.opts.print_next_steps_only {
+            print_next_steps(&home::read_configs(&self.opts.home_path));
+            return;
+        }
+
+        if let Some(count) = self.opts.list_derived_accounts {
+            let (_, _, wallet) = wallet::get_account_from_prompt();
+            list_derived_accounts(&wallet, count);
+            return;
+        }
+
+        // the status log is already set, so the intro goes to it too
+        let opts = WizardOptions {
+            status_log: None,
+            ..self.opts.clone()
+        };
+        if !opts.no_intro {
+            let intro = opts
+                .intro_text
+                .clone()
+                .unwrap_or_else(|| intro_text(opts.skip_mining));
+            status_info!("\nValidator Config Wizard.", &intro);
+        }
+
+        let swarm = entrypoint::get_args().swarm_path.is_some();
+        let token = CancelToken::new();
+        cancel::install_handler(token.clone());
+        match run_wizard(&opts, swarm, &token) {
+            Ok(output) => {
+                if !opts.operator_only {
+                    print_next_steps(&output.app_config)
+                }
+            }
+            Err(e) if e.is::<cancel::Cancelled>() => {
+                cancel::report_removed(cancel::remove_partial());
+                exit(cancel::CANCELLED_EXIT_CODE)
+            }
+            Err(e) => {
+                println!("ERROR: {:#}, exiting.", e);
+                exit(1)
+            }
+        }
+    }
+}
+
+/// The waypoint of the key store is the one of 0L.toml, or the genesis waypoint written with it
+fn check_written_waypoint(ks: &key_store::ExistingKeyStore, app_config: &AppCfg) -> Result<(), Error> {
+    let written = match ks.waypoint()? {
+        Some(w) => w,
+        // at genesis there is no waypoint until genesis is built
+        None if app_config.chain_info.base_waypoint.is_none() => return Ok(()),
+        None => bail!("0L.toml has a waypoint but the key store has none"),
+    };
+    let genesis_waypoint = fs::read_to_string(app_config.workspace.node_home.join("genesis_waypoint"))
+        .ok()
+        .and_then(|w| w.trim().parse::<Waypoint>().ok());
+    if Some(written) == app_config.chain_info.base_waypoint || Some(written) == genesis_waypoint {
+        Ok(())
+    } else {
+        bail!(
+            "the key store waypoint {} is not the one of 0L.toml, {:?}, or the genesis_waypoint",
+            written,
+            app_config.chain_info.base_waypoint
+        )
+    }
+}
+
+/// An epoch without its waypoint, or a waypoint without its epoch, isn't used by the configs,
+/// which fetch both from the upstream peer instead, so it is an error.
+pub fn check_epoch_waypoint(epoch: Option<u64>, waypoint: Option<Waypoint>) -> Result<(), Error> {
+    match (epoch, waypoint) {
+        (Some(e), None) => bail!(
+            "--epoch {} was passed without a waypoint, pass --waypoint or --waypoint-file too, or --epoch-from-chain to fetch both",
+            e
+        ),
+        (None, Some(w)) => bail!(
+            "waypoint {} was passed without its epoch, pass --epoch too, or --epoch-from-chain to fetch both",
+            w
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// the waypoint in a file, e.g. genesis_waypoint from a genesis repo
+pub fn read_waypoint_file(path: &PathBuf) -> Result<Waypoint, Error> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("could not read waypoint file {:?}", path))?;
+    text.trim().parse().map_err(|e| {
+        Error::msg(format!(
+            "{:?} does not contain a waypoint, expected the version:hash form: {:?}",
+            path, e
+        ))
+    })
+}
 
+/// public ipv4 of the node in a manifest's fullnode address
+fn manifest_ip(manifest: &ValConfigs) -> Option<Ipv4Addr> {
+    match manifest.op_fullnode_network_addresses_string.find_ip_addr() {
+        Some(IpAddr::V4(ip)) => Some(ip),
+        _ => None,
+    }
+}
+
+/// write a block zero proof to the blocks dir, as mining would
+fn write_block_zero(app_config: &AppCfg, block: &Block) -> Result<(), Error> {
+    let block_dir = app_config.get_block_dir();
+    let json = serde_json::to_string(block)?;
+    fs::create_dir_all(&block_dir)
+        .and_then(|_| fs::write(block_dir.join("block_0.json"), json))
+        .context("could not write block_0.json")
+}
+
+/// the closing guidance of the wizard, for the configs of a node
+pub fn print_next_steps(app_config: &AppCfg) {
+    if !app_config.get_block_dir().join("block_0.json").exists() {
         status_info!(
-            "Your validator node and miner app are now configured.", 
-            &format!(
-                "\nStart your node with `ol start`, and then ask someone with GAS to do this transaction `txs create-validator -u http://{}`",
-                &app_config.profile.ip
-            )
+            "Your validator node is configured, but block zero is pending.",
+            "\nMine it with `miner zero`, then add the proof to account.json with `onboard fix --import-block-zero <path to block_0.json>`"
         );
+        return;
+    }
+
+    status_info!(
+        "Your validator node and miner app are now configured.", 
+        &format!(
+            "\nOpen the ports printed by `onboard listen-addresses`. Start your node with `ol start`, and then ask someone with GAS to do this transaction for account {} `txs create-validator -u http://{}`",
+            &app_config.profile.account,
+            &app_config.profile.ip
+        )
+    );
+}
+
+/// auth key and account of an existing key store, for --reuse-keys. The auth key can't be
+/// recovered from the key store, it comes from --auth-key or the 0L.toml in the node home.
+pub fn reused_account(
+    home_path: &PathBuf,
+    auth_key: &Option<AuthenticationKey>,
+    namespace_suffix: &str,
+) -> Result<(AuthenticationKey, AccountAddress), Error> {
+    let existing = key_store::read_key_store_with_suffix(home_path, namespace_suffix)
+        .context("cannot reuse the key store")?;
+
+    let authkey = auth_key.or_else(|| {
+        parse_toml(home_path.join(CONFIG_FILE).to_str()?.to_owned())
+            .ok()
+            .and_then(|cfg| cfg.profile.auth_key.parse().ok())
+    });
+    match authkey {
+        Some(a) if a.derived_address() == existing.account => {
+            println!("Reusing key store {:?} of account {}", existing.path, existing.account);
+            Ok((a, existing.account))
+        }
+        Some(a) => bail!("auth key {} is not for account {} of the key store", a, existing.account),
+        None => bail!("could not find the auth key of the key store, pass --auth-key"),
+    }
+}
+
+/// default maximum percent of balance, or of change, autopay instructions can commit
+pub const DEFAULT_AUTOPAY_PERCENT_CAP: f64 = 50f64;
+
+/// autopay file looked up in the node home, without a template or --autopay-file
+pub const DEFAULT_AUTOPAY_FILE: &str = "autopay_batch.json";
+
+/// a wrong passphrase silently derives another account, so the operator checks it
+pub fn confirm_passphrase_account(account: AccountAddress) -> Result<(), Error> {
+    println!("Account derived from the mnemonic and passphrase: {}", account);
+    if *IS_TEST {
+        return Ok(());
     }
+    match Confirm::new().with_prompt("Is this the account you expect?").interact() {
+        Ok(true) => Ok(()),
+        _ => bail!("account not confirmed, check the passphrase"),
+    }
+}
+
+/// parse key=value notes for the metadata section of 0L.toml
+pub fn parse_profile_notes(notes: &[String]) -> Result<BTreeMap<String, String>, Error> {
+    notes
+        .iter()
+        .map(|n| match n.splitn(2, '=').collect::<Vec<&str>>().as_slice() {
+            [key, value] if !key.trim().is_empty() => {
+                Ok((key.trim().to_owned(), value.trim().to_owned()))
+            }
+            _ => bail!("profile note {:?} is not in the key=value form", n),
+        })
+        .collect()
+}
+
+/// number of tries the operator has to type back a generated mnemonic
+const MNEMONIC_CONFIRM_ATTEMPTS: u8 = 3;
+
+/// generate a fresh mnemonic from the OS rng, and make sure the operator recorded it
+pub fn generate_mnemonic() -> Result<WalletLibrary, Error> {
+    let (_, _, wallet, mnemonic_string) = wallet::keygen();
+    if *IS_TEST {
+        return Ok(wallet);
+    }
+    for _ in 0..MNEMONIC_CONFIRM_ATTEMPTS {
+        if wallet::confirm_mnemonic_from_prompt(&mnemonic_string) {
+            return Ok(wallet);
+        }
+        println!("WARN: the mnemonic entered does not match, try again.");
+    }
+    bail!("could not confirm the generated mnemonic. Nothing was written, run the wizard again")
+}
+
+/// intro to the wizard, with an estimate of the mining time at the current difficulty
+pub fn intro_text(skip_mining: bool) -> String {
+    let mut txt = "Next you'll enter your mnemonic and some other info to configure your validator node and on-chain account. If you haven't yet generated keys, run the standalone keygen tool with 'ol keygen'.".to_owned();
+    if !skip_mining {
+        // 15 minutes on modern CPUs at the production difficulty of 5,000,000
+        let minutes = 15 * delay::delay_difficulty() / 5_000_000;
+        let estimate = if minutes > 0 {
+            format!("up to {} minutes", minutes)
+        } else {
+            "less than a minute".to_owned()
+        };
+        txt.push_str(&format!(
+            "\n\nYour first 0L proof-of-work will be mined now. Expect this to take {} on modern CPUs.",
+            estimate
+        ));
+    }
+    txt.push('\n');
+    txt
+}
+
+/// Where the autopay instructions are read from, and how they are checked and signed
+#[derive(Clone, Debug, Default)]
+pub struct AutopayOptions {
+    /// template the instructions were downloaded from, saved as template.json
+    pub template: Option<Url>,
+    /// autopay file or directory, instead of the one in the home path
+    pub file_path: Option<PathBuf>,
+    /// file name in the home path, when there is no template or file path
+    pub default_name: Option<String>,
+    /// sequence number of the first transaction, instead of querying the upstream peer
+    pub seq_start: Option<u64>,
+    /// peer to query the sequence number and the destinations on
+    pub upstream: Option<Url>,
+    /// index of the owner account in the wallet
+    pub account_index: u64,
+    /// epoch the instructions start from, instead of the one of the chain info
+    pub base_epoch: Option<u64>,
+    /// epochs added to the start of every instruction
+    pub start_offset: Option<i64>,
+    /// have the operator review the instructions before signing
+    pub review: bool,
+    /// percent of balance or change the instructions may commit, unchecked if None
+    pub percent_cap: Option<f64>,
+    /// skip invalid instructions instead of exiting
+    pub continue_on_error: bool,
+    /// check the destination accounts exist on the upstream peer
+    pub validate_destinations: bool,
 }
 
 /// get autopay instructions from file
 pub fn get_autopay_batch(
-    template: &Option<Url>,
-    file_path: &Option<PathBuf>,
     home_path: &PathBuf,
     cfg: &AppCfg,
     wallet: &WalletLibrary,
     is_swarm: bool,
     is_genesis: bool,
-) -> (Option<Vec<PayInstruction>>, Option<Vec<SignedTransaction>>) {
-    let file_name = if template.is_some() {
-        // assumes the template was downloaded from URL
+    opts: &AutopayOptions,
+) -> Result<(Option<Vec<PayInstruction>>, Option<Vec<SignedTransaction>>), Error> {
+    let file_name = if opts.template.is_some() {
+        // assumes the template was downloaded from URL, it's saved with this name
         "template.json"
     } else {
-        "autopay_batch.json"
+        opts.default_name.as_deref().unwrap_or(DEFAULT_AUTOPAY_FILE)
     };
 
-    let starting_epoch = cfg.chain_info.base_epoch.unwrap_or(0);
-    let instr_vec = PayInstruction::parse_autopay_instructions(
-        &file_path.clone().unwrap_or(home_path.join(file_name)),
-        Some(starting_epoch.clone()),
-        None,
-    )
-    .unwrap();
+    let starting_epoch = opts.base_epoch.or(cfg.chain_info.base_epoch).unwrap_or(0);
+    let autopay_path = opts.file_path.clone().unwrap_or(home_path.join(file_name));
+    let instr_vec = if autopay_path.is_dir() {
+        let (instr_vec, counts) =
+            PayInstruction::parse_autopay_dir(&autopay_path, Some(starting_epoch), None, opts.start_offset)
+                .with_context(|| format!("could not parse autopay directory {:?}", &autopay_path))?;
+        for (f, n) in counts {
+            println!("{} autopay instructions from {:?}", n, f);
+        }
+        instr_vec
+    } else {
+        PayInstruction::parse_autopay_instructions(&autopay_path, Some(starting_epoch), None, opts.start_offset)
+            .with_context(|| format!("could not parse autopay file {:?}", &autopay_path))?
+    };
+
+    if let Some(cap) = opts.percent_cap {
+        let (balance, change) = PayInstruction::percent_totals(&instr_vec);
+        if balance > cap || change > cap {
+            bail!(
+                "autopay commits {}% of balance and {}% of change, above the cap of {}%. Check the instructions, or use --force",
+                balance,
+                change,
+                cap
+            );
+        }
+    }
+
+    if is_genesis { return Ok((Some(instr_vec), None)) }
 
-    if is_genesis { return (Some(instr_vec), None ) }
+    let errors = autopay_batch_cmd::check_instructions(&instr_vec);
+    let instr_vec = if errors.is_empty() {
+        instr_vec
+    } else if opts.continue_on_error {
+        autopay_batch_cmd::print_instruction_errors(&errors);
+        let skipped: Vec<usize> = errors.iter().map(|e| e.index).collect();
+        let valid: Vec<PayInstruction> = instr_vec
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !skipped.contains(i))
+            .map(|(_, instr)| instr)
+            .collect();
+        println!(
+            "WARN: skipping the invalid instructions, continuing with {} valid ones.",
+            valid.len()
+        );
+        valid
+    } else {
+        autopay_batch_cmd::print_instruction_errors(&errors);
+        bail!("fix the autopay instructions and run again, or use --continue-on-autopay-error");
+    };
+    if opts.validate_destinations {
+        match (&opts.upstream, cfg.chain_info.base_waypoint) {
+            (Some(url), Some(wp)) => precheck::check_autopay_destinations(url, wp, &instr_vec),
+            _ => println!("WARN: --validate-autopay-destinations needs an upstream peer and a waypoint, skipping"),
+        }
+    }
+    // signing is irreversible, have the operator review the set unless bypassed
+    let instr_vec = if opts.review && !*IS_TEST {
+        autopay_batch_cmd::review_instructions(instr_vec)?
+    } else {
+        instr_vec
+    };
 
+    let txn_vec = sign_autopay_instructions(
+        &instr_vec,
+        cfg,
+        wallet,
+        opts.account_index,
+        is_swarm,
+        opts.seq_start,
+        &opts.upstream,
+    )?;
+    Ok((Some(instr_vec), Some(txn_vec)))
+}
 
-    let script_vec = autopay_batch_cmd::process_instructions(instr_vec.clone());
+/// Signs the autopay instructions as the owner at the account index, from seq_start, or else the
+/// sequence number of the account on the upstream peer, or 0 for a new account. Errors if there
+/// is no seq_start and the upstream peer can't be queried.
+pub fn sign_autopay_instructions(
+    instr_vec: &[PayInstruction],
+    cfg: &AppCfg,
+    wallet: &WalletLibrary,
+    account_index: u64,
+    is_swarm: bool,
+    seq_start: Option<u64>,
+    upstream: &Option<Url>,
+) -> Result<Vec<SignedTransaction>, Error> {
+    let script_vec = match autopay_batch_cmd::encode_instructions(instr_vec.to_vec()) {
+        Ok(scripts) => scripts,
+        Err(errors) => {
+            autopay_batch_cmd::print_instruction_errors(&errors);
+            bail!("fix the autopay instructions and run again");
+        }
+    };
     let url = cfg.what_url(false);
     let mut tx_params = submit_tx::get_tx_params_from_toml(
         cfg.to_owned(),
@@ -250,7 +1886,15 @@ pub fn get_autopay_batch(
         None,
         is_swarm,
     )
-    .unwrap();
+    .context("could not get the tx params of the configs")?;
+    if account_index > 0 {
+        // the tx params are for the default account, sign as the owner at this index instead
+        let keys = KeyScheme::new_with_index(wallet, account_index);
+        tx_params.keypair = KeyPair::from(keys.child_0_owner.get_private_key());
+        tx_params.auth_key = keys.derived_auth_key();
+        tx_params.signer_address = keys.derived_address();
+        tx_params.owner_address = keys.derived_address();
+    }
     let tx_expiration_sec = if *IS_TEST {
         // creating fixtures here, so give it near infinite expiry
         100 * 360 * 24 * 60 * 60
@@ -259,8 +1903,152 @@ pub fn get_autopay_batch(
         7 * 24 * 60 * 60
     };
     tx_params.tx_cost.user_tx_timeout = tx_expiration_sec;
-    let txn_vec = autopay_batch_cmd::sign_instructions(script_vec, 0, &tx_params);
-    (Some(instr_vec), Some(txn_vec))
+
+    // a brand new account starts at 0, an existing one must continue from its on-chain sequence number
+    let starting_sequence_num = match (seq_start, upstream) {
+        (Some(n), _) => n,
+        // the test fixtures are signed for fresh accounts, without a chain to query
+        (None, _) if *IS_TEST => 0,
+        (None, Some(url)) => get_sequence_number(url, &tx_params.waypoint, tx_params.signer_address)
+            .context("pass the sequence number to start the autopay txs from, e.g. --autopay-seq-start")?,
+        (None, None) => bail!(
+            "no upstream peer to fetch the sequence number of {} from, pass the sequence number to start the autopay txs from, e.g. --autopay-seq-start",
+            tx_params.signer_address
+        ),
+    };
+    let txn_vec = autopay_batch_cmd::sign_instructions(script_vec, starting_sequence_num, &tx_params);
+
+    // a derivation bug would sign as another account, and the txs would silently fail on chain
+    let account = cfg.profile.account;
+    if let Some(tx) = txn_vec.iter().find(|tx| tx.sender() != account) {
+        bail!(
+            "autopay tx with sequence number {} is signed by {}, not the configured account {}",
+            tx.sequence_number(),
+            tx.sender(),
+            account
+        );
+    }
+    Ok(txn_vec)
+}
+
+/// hashes of the signed autopay transactions, to look them up on chain after submission
+pub fn autopay_tx_hashes(signed: &[SignedTransaction]) -> Vec<HashValue> {
+    signed
+        .iter()
+        .map(|tx| Transaction::UserTransaction(tx.clone()).hash())
+        .collect()
+}
+
+/// the url with its port replaced, errors if the url can't have a port
+pub fn url_with_port(url: &Url, port: u16) -> Result<Url, Error> {
+    let mut url = url.to_owned();
+    if url.set_port(Some(port)).is_err() {
+        bail!(
+            "the URL {} is malformed, expected the scheme://host form, e.g. http://1.2.3.4",
+            url
+        );
+    }
+    Ok(url)
+}
+
+/// Errors unless the URL is https, for --strict-url-scheme, naming where the URL came from
+pub fn check_strict_scheme(source: &str, url: &str) -> Result<(), Error> {
+    match Url::parse(url) {
+        Ok(u) if u.scheme() == "https" => Ok(()),
+        _ => bail!("--strict-url-scheme rejects {} {}, only https URLs are allowed", source, url),
+    }
+}
+
+/// Errors unless the template URL is an absolute http(s) URL with a host, which the
+/// port and path of the template can be set on
+pub fn check_template_url(url: &Url) -> Result<(), Error> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        bail!(
+            "template URL {} has the scheme {}, expected an http(s) URL, e.g. http://1.2.3.4",
+            url,
+            url.scheme()
+        );
+    }
+    if url.cannot_be_a_base() || url.host_str().map(|h| h.is_empty()).unwrap_or(true) {
+        bail!("template URL {} has no host, expected the http://host form, e.g. http://1.2.3.4", url);
+    }
+    Ok(())
+}
+
+/// The most gas each autopay transaction can cost, max_gas_amount times gas_unit_price, in micro units.
+/// The node has no gas estimation, so this is the bound the account must be funded for.
+pub fn gas_bound(signed: &[SignedTransaction]) -> Vec<u64> {
+    signed
+        .iter()
+        .map(|tx| tx.max_gas_amount().saturating_mul(tx.gas_unit_price()))
+        .collect()
+}
+
+/// Prints the gas bound of each autopay instruction's transaction, and the total
+pub fn print_gas_bound(instructions: &[PayInstruction], signed: &[SignedTransaction]) {
+    let bounds = gas_bound(signed);
+    bounds.iter().enumerate().for_each(|(i, b)| {
+        let uid = instructions.get(i).and_then(|inst| inst.uid);
+        println!("autopay instruction {:?}: at most {} of gas", uid, format_coin(*b));
+    });
+    let total = bounds.iter().fold(0u64, |sum, b| sum.saturating_add(*b));
+    println!(
+        "Autopay transactions cost at most {} of gas, fund the account with at least that much",
+        format_coin(total)
+    );
+}
+
+/// the web monitor URL to fetch a template from. Defaults to the 3030 web port when the URL
+/// has no port, unless keep_port is set, e.g. for templates served on 443.
+pub fn template_web_url(url: &Url, keep_port: bool) -> Result<Url, Error> {
+    if keep_port || url.port().is_some() {
+        Ok(url.to_owned())
+    } else {
+        url_with_port(url, 3030)
+    }
+}
+
+/// current epoch and waypoint of the chain, from the web monitor of the upstream peer
+pub fn chain_epoch_waypoint(upstream: &Url) -> Result<(u64, Waypoint), Error> {
+    let mut web_monitor_url = upstream.clone();
+    web_monitor_url
+        .set_port(Some(3030))
+        .map_err(|_| Error::msg(format!("upstream peer URL {} is malformed", upstream)))?;
+    bootstrap_waypoint_from_upstream(&web_monitor_url.join("epoch.json")?)
+}
+
+/// offer the current epoch of the chain as the base epoch for autopay, if it differs from the configs
+pub fn offer_chain_epoch(upstream: &Url, cfg_epoch: Option<u64>) -> Option<u64> {
+    if *IS_TEST {
+        return None;
+    }
+    let (epoch, _) = chain_epoch_waypoint(upstream).ok()?;
+    if Some(epoch) == cfg_epoch {
+        return None;
+    }
+    let txt = format!(
+        "The chain is at epoch {}, but the configs have base epoch {:?}. Use epoch {} for relative autopay instructions?",
+        epoch, cfg_epoch, epoch
+    );
+    match Confirm::new().with_prompt(txt).interact() {
+        Ok(true) => Some(epoch),
+        _ => None,
+    }
+}
+
+/// query the upstream peer for the account's sequence number, an account not yet on chain starts at 0.
+/// Errors if the peer can't be queried, signing from a guessed number would make the txs fail on chain.
+pub fn get_sequence_number(url: &Url, waypoint: &Waypoint, account: AccountAddress) -> Result<u64, Error> {
+    let view = make_client(Some(url.to_owned()), *waypoint)
+        .and_then(|c| c.get_account(&account))
+        .with_context(|| format!("could not fetch the sequence number of {} from {}", account, url))?;
+    match view {
+        Some(view) => {
+            println!("Account found on chain, signing autopay from sequence number: {}", view.sequence_number);
+            Ok(view.sequence_number)
+        }
+        None => Ok(0),
+    }
 }
 
 /// save template file
@@ -277,25 +2065,76 @@ pub fn save_template(url: &Url, home_path: &PathBuf) -> PathBuf {
     g_path
 }
 
-/// Creates an account.json file for the validator
+/// Prints which child of the mnemonic each key role was derived from
+pub fn print_key_derivation(keys: &KeyScheme) {
+    println!("Key derivation of account index {}:", keys.account_index);
+    println!("{:<20} {:>6}  {}", "role", "child", "public key");
+    keys.derivations().iter().for_each(|d| {
+        println!("{:<20} {:>6}  {}", d.role, d.child_number, d.public_key);
+    });
+}
+
+/// print the accounts at the first derivation indexes of the mnemonic
+pub fn list_derived_accounts(wallet: &WalletLibrary, count: u64) {
+    println!("Accounts derived from this mnemonic, use --account-index to select one:");
+    (0..count).for_each(|i| {
+        println!("index {}: {}", i, KeyScheme::new_with_index(wallet, i).derived_address());
+    });
+}
+
+/// Creates an account.json file for the validator, checked for the private keys of the scheme
 pub fn write_account_json(
     json_path: &Option<PathBuf>,
-    wallet: WalletLibrary,
+    keys: KeyScheme,
     wizard_config: Option<AppCfg>,
     autopay_batch: Option<Vec<PayInstruction>>,
     autopay_signed: Option<Vec<SignedTransaction>>,
-) {
+    include_private: bool,
+    operator: Option<OperatorConfigs>,
+) -> Result<(), Error> {
     let cfg = wizard_config.unwrap_or(app_config().clone());
     let json_path = json_path.clone().unwrap_or(cfg.workspace.node_home.clone());
-    let keys = KeyScheme::new(&wallet);
-    let block = Block::parse_block_file(cfg.get_block_dir().join("block_0.json").to_owned());
-
-    ValConfigs::new(
-        block,
-        keys,
-        cfg.profile.ip.to_string(),
-        autopay_batch,
-        autopay_signed,
-    )
-    .create_manifest(json_path);
+    let block_path = cfg.get_block_dir().join("block_0.json");
+    // without a block zero, the manifest is still written so mining can be done separately
+    let proof_pending = !block_path.exists();
+    let block = if proof_pending {
+        Block::default()
+    } else {
+        Block::parse_block_file(block_path)
+    };
+
+    let private_keys = keys.private_key_bytes();
+    let mut manifest = match operator {
+        Some(op) => ValConfigs::from_operator(block, op, autopay_batch, autopay_signed),
+        None => ValConfigs::new(
+            block,
+            keys,
+            cfg.profile.ip.to_string(),
+            autopay_batch,
+            autopay_signed,
+        ),
+    };
+    manifest.proof_pending = proof_pending;
+    manifest.contact = cfg.metadata.get(CONTACT_METADATA_KEY).cloned();
+    manifest.create_manifest_checked(json_path, &private_keys, include_private)
+}
+
+#[test]
+fn test_check_strict_scheme() {
+    assert!(check_strict_scheme("--upstream-peer", "https://1.2.3.4:8080").is_ok());
+    assert!(check_strict_scheme("--upstream-peer", "http://1.2.3.4:8080").is_err());
+    assert!(check_strict_scheme("the seed peer", "github://OLSF/experimental-genesis").is_err());
+    assert!(check_strict_scheme("--template-url", "1.2.3.4").is_err());
+}
+
+#[test]
+fn test_check_template_url() {
+    let check = |u: &str| check_template_url(&Url::parse(u).unwrap());
+
+    assert!(check("http://1.2.3.4").is_ok());
+    assert!(check("https://node.example.com:8080/").is_ok());
+    assert!(check("ftp://1.2.3.4").is_err());
+    // a bare host:port parses with the host as the scheme
+    assert!(check("localhost:8080").is_err());
+    assert!(check("mailto:node@example.com").is_err());
 }
@@ -6,7 +6,7 @@ use abscissa_core::{
 };
 use std::path::PathBuf;
 use crate::commands;
-use diem_global_constants::NODE_HOME;
+use crate::home;
 
 /// Toplevel entrypoint command.
 ///
@@ -44,6 +44,10 @@ where
     #[options(help = "use the fixtures of a persona, e.g. alice, eve")]
     pub swarm_persona: Option<String>,
 
+    /// Plain output without ANSI colors
+    #[options(no_short, help = "no colors in the output, also set by the NO_COLOR environment variable")]
+    pub no_color: bool,
+
 }
 
 impl<Cmd> EntryPoint<Cmd>
@@ -134,8 +138,7 @@ pub fn get_args() -> EntryPointTxsCmd {
 /// in case of swarm like "....../swarm_temp/0" for alice
 /// in case of swarm like "....../swarm_temp/1" for bob
 pub fn get_node_home() -> PathBuf {
-    let mut config_path = dirs::home_dir().unwrap();
-    config_path.push(NODE_HOME);
+    let mut config_path = home::default_home();
 
     let entry_args = get_args();
 
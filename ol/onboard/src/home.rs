@@ -1,9 +1,71 @@
 //! get home path or set it
 use dialoguer::{Confirm, Input};
 use diem_global_constants::NODE_HOME;
-use std::path::PathBuf;
+use anyhow::{bail, Context, Error};
+use ol_types::config::{parse_toml, AppCfg};
+use std::{env, path::PathBuf, process::exit};
 
-use crate::entrypoint::get_args;
+use crate::{commands::CONFIG_FILE, entrypoint::get_args};
+
+/// Environment variable with the node home, used when --home-path isn't passed
+pub const NODE_HOME_ENV: &str = "OL_NODE_HOME";
+
+/// Default node home of the platform: ~/.0L on unix, where the other 0L tools look for it,
+/// and the user's data directory elsewhere, e.g. %APPDATA%\0L on windows.
+pub fn default_home() -> PathBuf {
+    try_default_home().unwrap_or_else(|e| exit_with(e))
+}
+
+/// Like default_home, with an error instead of exiting
+pub fn try_default_home() -> Result<PathBuf, Error> {
+    let base = if cfg!(unix) { dirs::home_dir() } else { dirs::data_dir() };
+    match base {
+        Some(dir) if cfg!(unix) => Ok(dir.join(NODE_HOME)),
+        Some(dir) => Ok(dir.join("0L")),
+        None => bail!("could not find a home directory for node data, pass --home-path"),
+    }
+}
+
+/// The node home from --home-path, or else from OL_NODE_HOME. Exits if the path isn't usable as a home.
+pub fn home_override(flag: &Option<PathBuf>) -> Option<PathBuf> {
+    try_home_override(flag).unwrap_or_else(|e| exit_with(e))
+}
+
+/// Like home_override, with an error instead of exiting
+pub fn try_home_override(flag: &Option<PathBuf>) -> Result<Option<PathBuf>, Error> {
+    let (path, source) = match flag {
+        Some(p) => (p.clone(), "--home-path".to_owned()),
+        None => match env::var(NODE_HOME_ENV) {
+            Ok(v) if !v.trim().is_empty() => (PathBuf::from(v.trim()), NODE_HOME_ENV.to_owned()),
+            _ => return Ok(None),
+        },
+    };
+    if path.exists() && !path.is_dir() {
+        bail!("{} {:?} is not a directory", source, &path);
+    }
+    Ok(Some(path))
+}
+
+/// The 0L.toml of a configured node, from --home, $OL_NODE_HOME, or the default home.
+/// The onboard app doesn't load 0L.toml itself, so commands on an existing node read it here.
+pub fn read_configs(flag: &Option<PathBuf>) -> AppCfg {
+    try_read_configs(flag).unwrap_or_else(|e| exit_with(e))
+}
+
+/// Like read_configs, with an error instead of exiting
+pub fn try_read_configs(flag: &Option<PathBuf>) -> Result<AppCfg, Error> {
+    let home = match try_home_override(flag)? {
+        Some(home) => home,
+        None => try_default_home()?,
+    };
+    parse_toml(home.join(CONFIG_FILE).to_string_lossy().to_string())
+        .with_context(|| format!("could not read the configs in {:?}", &home))
+}
+
+fn exit_with(e: Error) -> ! {
+    println!("ERROR: {:#}, exiting.", e);
+    exit(1);
+}
 
 /// interact with user to get the home path for files
 pub fn what_home(_swarm_path: Option<PathBuf>, _swarm_persona: Option<String>) -> PathBuf {
@@ -12,8 +74,7 @@ pub fn what_home(_swarm_path: Option<PathBuf>, _swarm_persona: Option<String>) -
       return swarm_home(path, args.swarm_persona);
     }
 
-    let mut default_home_dir = dirs::home_dir().unwrap();
-    default_home_dir.push(NODE_HOME);
+    let default_home_dir = default_home();
 
     let txt = &format!(
         "Will you use the default directory for node data and configs: {:?}?",
@@ -0,0 +1,44 @@
+//! discover the public ip of the node
+
+use anyhow::{bail, Error};
+use reqwest::Url;
+use std::net::Ipv4Addr;
+
+/// Default service which echoes back the ip address of the caller
+pub const IP_ECHO_SERVICE: &str = "https://api.ipify.org";
+
+/// Query an ip-echo service for the public ip address of this host.
+/// A private or loopback address is rejected, since peers could not reach it.
+pub fn detect_public_ip(service: &Url) -> Result<Ipv4Addr, Error> {
+    let body = reqwest::blocking::get(service.as_str())?.text()?;
+    let ip: Ipv4Addr = body.trim().parse()?;
+    if !is_public(&ip) {
+        bail!("{} is not a public address", ip);
+    }
+    Ok(ip)
+}
+
+/// Whether peers on the internet could reach this address
+pub fn is_public(ip: &Ipv4Addr) -> bool {
+    !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified())
+}
+
+/// Use the ip from args, or try to detect it. None means the user will be asked.
+pub fn what_ip(ip: Option<Ipv4Addr>, autodetect: bool, service: &Option<Url>) -> Option<Ipv4Addr> {
+    if ip.is_some() || !autodetect {
+        return ip;
+    }
+    let service = service
+        .clone()
+        .unwrap_or_else(|| Url::parse(IP_ECHO_SERVICE).unwrap());
+    match detect_public_ip(&service) {
+        Ok(ip) => {
+            println!("Detected public IP address: {}", ip);
+            Some(ip)
+        }
+        Err(e) => {
+            println!("WARN: could not detect public IP address from {}, message: {:?}", service, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Read an existing key_store.json written by a previous wizard run
+
+use anyhow::{bail, Context, Error};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    x25519,
+};
+use diem_global_constants::{
+    CONSENSUS_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY, OPERATOR_KEY, OWNER_ACCOUNT, SAFETY_DATA,
+    VALIDATOR_NETWORK_KEY, WAYPOINT,
+};
+use diem_secure_storage::{CryptoStorage, KVStorage, Namespaced, OnDiskStorage, Storage};
+use diem_types::{
+    account_address::{self, AccountAddress},
+    network_address::NetworkAddress,
+    waypoint::Waypoint,
+};
+use serde_json::{Map, Value};
+use std::{fs, path::{Path, PathBuf}};
+
+pub use ol_types::key_store_checksum::{
+    verify_checksum, write_checksum, ChecksumStatus, CHECKSUM_FILE, KEY_STORE_FILE,
+};
+
+/// 0L convention for the operator namespace, the account appended by '-oper'
+pub const DEFAULT_NAMESPACE_SUFFIX: &str = "-oper";
+
+/// Entries of the operator namespace the wizard writes, which must all be present to reuse a key store
+pub const OPERATOR_ENTRIES: [&str; 7] = [
+    OPERATOR_KEY,
+    OWNER_ACCOUNT,
+    CONSENSUS_KEY,
+    VALIDATOR_NETWORK_KEY,
+    FULLNODE_NETWORK_KEY,
+    EXECUTION_KEY,
+    SAFETY_DATA,
+];
+
+/// Keys which can be replaced on their own, without onboarding again
+pub const SETTABLE_KEYS: [&str; 2] = [VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY];
+
+/// A key store found on disk, and the account it belongs to
+#[derive(Debug)]
+pub struct ExistingKeyStore {
+    /// path of key_store.json
+    pub path: PathBuf,
+    /// the owner account, which is also the namespace of the keys
+    pub account: AccountAddress,
+    /// appended to the account for the operator namespace
+    pub suffix: String,
+    /// parsed contents
+    pub entries: Map<String, Value>,
+}
+
+/// The suffix is appended to the account in the key store keys, which '/' separates, so only
+/// letters, digits, '-', '_' and '.' are allowed.
+pub fn check_namespace_suffix(suffix: &str) -> Result<(), Error> {
+    if suffix.is_empty() {
+        bail!("the namespace suffix is empty");
+    }
+    if let Some(c) = suffix
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.'))
+    {
+        bail!("the namespace suffix {} has {:?}, only letters, digits, '-', '_' and '.' are allowed", suffix, c);
+    }
+    Ok(())
+}
+
+/// Moves the operator entries of the account, which the key store init writes under
+/// DEFAULT_NAMESPACE_SUFFIX, to the namespace with another suffix. The checksum is written again.
+pub fn rename_operator_namespace(home_path: &Path, account: AccountAddress, suffix: &str) -> Result<(), Error> {
+    check_namespace_suffix(suffix)?;
+    let path = home_path.join(KEY_STORE_FILE);
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("could not read key store {:?}", &path))?;
+    let entries: Map<String, Value> = serde_json::from_str(&text)
+        .with_context(|| format!("key store {:?} is not valid json", &path))?;
+    let from = format!("{}{}/", account.to_hex(), DEFAULT_NAMESPACE_SUFFIX);
+    let to = format!("{}{}/", account.to_hex(), suffix);
+    let renamed: Map<String, Value> = entries
+        .into_iter()
+        .map(|(k, v)| match k.strip_prefix(&from) {
+            Some(name) => (format!("{}{}", to, name), v),
+            None => (k, v),
+        })
+        .collect();
+    fs::write(&path, serde_json::to_vec(&renamed)?)
+        .with_context(|| format!("could not write key store {:?}", &path))?;
+    write_checksum(home_path)?;
+    Ok(())
+}
+
+/// Reads the key store in the node home, and checks all the operator entries are present
+/// and the keys match the checksum, if one was written.
+pub fn read_key_store(home_path: &Path) -> Result<ExistingKeyStore, Error> {
+    read_key_store_with_suffix(home_path, DEFAULT_NAMESPACE_SUFFIX)
+}
+
+/// read_key_store, for an operator namespace with another suffix than DEFAULT_NAMESPACE_SUFFIX
+pub fn read_key_store_with_suffix(home_path: &Path, suffix: &str) -> Result<ExistingKeyStore, Error> {
+    let path = home_path.join(KEY_STORE_FILE);
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("could not read key store {:?}", &path))?;
+    let entries: Map<String, Value> = serde_json::from_str(&text)
+        .with_context(|| format!("key store {:?} is not valid json", &path))?;
+    verify_checksum(home_path)?;
+
+    let owner_entry = format!("{}/{}", suffix, OWNER_ACCOUNT);
+    let namespace = match entries.keys().find_map(|k| k.strip_suffix(&owner_entry)) {
+        Some(n) => n.to_owned(),
+        None => bail!("key store {:?} has no {} entry", &path, OWNER_ACCOUNT),
+    };
+    let account: AccountAddress = namespace
+        .parse()
+        .with_context(|| format!("key store namespace {} is not an account address", &namespace))?;
+
+    let missing: Vec<&str> = OPERATOR_ENTRIES
+        .iter()
+        .filter(|e| !entries.contains_key(&format!("{}{}/{}", namespace, suffix, e)))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        bail!("key store {:?} is missing entries: {}", &path, missing.join(", "));
+    }
+
+    Ok(ExistingKeyStore {
+        path,
+        account,
+        suffix: suffix.to_owned(),
+        entries,
+    })
+}
+
+/// Network identity of the node, as peers need it to connect
+#[derive(Debug)]
+pub struct PeerIdentity {
+    /// x25519 public key of the network
+    pub public_key: x25519::PublicKey,
+    /// peer id derived from the public key
+    pub peer_id: AccountAddress,
+    /// address with the noise and handshake protocols, for seed peers
+    pub address: NetworkAddress,
+}
+
+impl ExistingKeyStore {
+    /// Namespaced storage of the operator keys
+    fn operator_storage(&self) -> Storage {
+        Storage::NamespacedStorage(Namespaced::new(
+            format!("{}{}", self.account.to_hex(), self.suffix),
+            Box::new(Storage::OnDiskStorage(OnDiskStorage::new(self.path.clone()))),
+        ))
+    }
+
+    /// Replaces the key named key_name in the operator namespace, leaving the other entries as they are.
+    /// Errors before writing if the keys don't match the checksum.
+    pub fn set_key(&self, key_name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        if !SETTABLE_KEYS.contains(&key_name) {
+            bail!("{} can't be set on its own, only: {}", key_name, SETTABLE_KEYS.join(", "));
+        }
+        if let Some(home) = self.path.parent() {
+            verify_checksum(home)?;
+        }
+        self.operator_storage()
+            .import_private_key(key_name, key)
+            .with_context(|| format!("could not write {} to {:?}", key_name, &self.path))?;
+        // the key store changed on purpose, so the checksum is written again
+        match self.path.parent() {
+            Some(home) => write_checksum(home).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Public key of the key named key_name in the operator namespace
+    pub fn public_key(&self, key_name: &str) -> Result<Ed25519PublicKey, Error> {
+        Ok(self
+            .operator_storage()
+            .get_public_key(key_name)
+            .with_context(|| format!("could not read {} from {:?}", key_name, &self.path))?
+            .public_key)
+    }
+
+    /// Waypoint the node starts from, None if none was set yet
+    pub fn waypoint(&self) -> Result<Option<Waypoint>, Error> {
+        if !self.entries.contains_key(&format!("{}{}/{}", self.account.to_hex(), self.suffix, WAYPOINT)) {
+            return Ok(None);
+        }
+        let waypoint = self
+            .operator_storage()
+            .get::<Waypoint>(WAYPOINT)
+            .with_context(|| format!("could not read {} from {:?}", WAYPOINT, &self.path))?
+            .value;
+        Ok(Some(waypoint))
+    }
+
+    /// Identity of the network key named key_name, listening on the ip and port
+    pub fn peer_identity(
+        &self,
+        key_name: &'static str,
+        ip: &str,
+        port: u16,
+    ) -> Result<PeerIdentity, Error> {
+        let ed_key = self.public_key(key_name)?;
+        let public_key = x25519::PublicKey::from_ed25519_public_bytes(&ed_key.to_bytes())
+            .map_err(|e| Error::msg(format!("{} is not a network key: {:?}", key_name, e)))?;
+        let address: NetworkAddress = format!("/ip4/{}/tcp/{}", ip, port)
+            .parse()
+            .with_context(|| format!("{} is not an ip4 address", ip))?;
+        Ok(PeerIdentity {
+            public_key,
+            peer_id: account_address::from_identity_public_key(public_key),
+            address: address.append_prod_protos(public_key, 0),
+        })
+    }
+}
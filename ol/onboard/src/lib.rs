@@ -6,7 +6,7 @@
 
 // Tip: Deny warnings with `RUSTFLAGS="-D warnings"` environment variable in CI
 
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 #![warn(
     missing_docs,
     rust_2018_idioms,
@@ -23,4 +23,15 @@ pub mod prelude;
 pub mod entrypoint;
 pub mod manifest;
 pub mod home;
-pub mod read_genesis;
\ No newline at end of file
+pub mod read_genesis;
+pub mod cancel;
+pub mod chain_info;
+pub mod ip;
+pub mod precheck;
+pub mod key_store;
+pub mod status;
+pub mod systemd;
+pub mod build_genesis;
+pub mod registration;
+pub mod tarball;
+pub mod permissions;
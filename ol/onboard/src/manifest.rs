@@ -5,11 +5,22 @@
 use ol_types::{block::Block, config::AppCfg};
 use ol_keys::scheme::KeyScheme;
 
-use diem_types::transaction::SignedTransaction;
+use anyhow::{bail, Context, Error};
+use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
 use diem_wallet::WalletLibrary;
 use ol_types::{account::ValConfigs, pay_instruction::PayInstruction};
-use std::path::PathBuf;
-use crate::prelude::app_config;
+use reqwest::{StatusCode, Url};
+use std::{
+    collections::VecDeque,
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use crate::{ip, prelude::app_config};
+use serde::{Deserialize, Serialize};
 
 /// Creates an account.json file for the validator
 pub fn write_manifest(
@@ -39,4 +50,379 @@ pub fn write_manifest(
     ).create_manifest(miner_home);
 }
 
+/// The outcome of one check of an account manifest
+pub struct ManifestCheck {
+    /// what was checked
+    pub name: &'static str,
+    /// why the check failed, if it did
+    pub result: Result<(), Error>,
+}
+
+/// Reads an account.json
+pub fn read_manifest(path: &PathBuf) -> Result<ValConfigs, Error> {
+    let file = fs::File::open(path).with_context(|| format!("could not open {:?}", path))?;
+    let manifest: ValConfigs = serde_json::from_reader(file)
+        .with_context(|| format!("{:?} is not an account manifest", path))?;
+    Ok(manifest)
+}
+
+/// Checks the block zero of the manifest is the block_0.json of the block dir, and that both
+/// claim the difficulty it was mined with, as returned by write_genesis.
+pub fn check_proof_difficulty(manifest: &ValConfigs, block_dir: &PathBuf, mined: u64) -> Result<(), Error> {
+    let block_path = block_dir.join("block_0.json");
+    let block: Block = serde_json::from_str(
+        &fs::read_to_string(&block_path).with_context(|| format!("could not read {:?}", &block_path))?,
+    )
+    .with_context(|| format!("{:?} is not a block", &block_path))?;
+    miner::block::check_genesis_difficulty(&block, block_dir, mined)?;
+    if manifest.block_zero.preimage != block.preimage {
+        bail!("the block zero of the manifest is not {:?}", &block_path);
+    }
+    match miner::block::preimage_difficulty(&manifest.block_zero.preimage) {
+        Some(d) if d == mined => Ok(()),
+        Some(d) => bail!("the manifest records the difficulty {}, block zero was mined with {}", d, mined),
+        None => bail!("the preimage of the manifest's block zero is too short to have a difficulty"),
+    }
+}
+
+/// Sidecar of the manifest, with the metrics of mining its block zero
+pub const MINING_FILE: &str = "mining.json";
+
+/// How block zero was mined, as measured by the wizard
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MiningMetrics {
+    /// wall time of mining and writing block zero, in seconds
+    pub duration_secs: f64,
+    /// seconds in the VDF, as recorded in the block
+    pub vdf_secs: u64,
+    /// VDF iterations block zero was mined with
+    pub iterations: u64,
+    /// iterations per second in the VDF
+    pub iterations_per_sec: f64,
+}
+
+impl MiningMetrics {
+    /// Metrics of a block zero mined with the iterations in vdf_secs, out of elapsed
+    pub fn new(elapsed: Duration, vdf_secs: u64, iterations: u64) -> Self {
+        let iterations_per_sec = if vdf_secs > 0 { iterations as f64 / vdf_secs as f64 } else { 0f64 };
+        MiningMetrics {
+            duration_secs: elapsed.as_secs_f64(),
+            vdf_secs,
+            iterations,
+            iterations_per_sec,
+        }
+    }
+
+    /// One line for the status log
+    pub fn summary(&self) -> String {
+        format!(
+            "block zero took {:.1}s ({}s in the VDF), {} iterations at {:.0} iterations/sec",
+            self.duration_secs, self.vdf_secs, self.iterations, self.iterations_per_sec
+        )
+    }
+
+    /// Writes the metrics to mining.json in the manifest dir
+    pub fn write(&self, manifest_dir: &PathBuf) -> Result<(), Error> {
+        let path = manifest_dir.join(MINING_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("could not write the mining metrics to {:?}", path))
+    }
+}
+
+/// Runs all checks on an account.json produced elsewhere: the block zero proof,
+/// the signed autopay transactions, and the ip address of the node.
+pub fn validate_manifest(path: &PathBuf) -> Result<Vec<ManifestCheck>, Error> {
+    let manifest = read_manifest(path)?;
+    let owner: AccountAddress = manifest.ow_human_name.parse()?;
+
+    Ok(vec![
+        ManifestCheck {
+            name: "block zero proof",
+            result: if manifest.proof_pending {
+                Err(anyhow::anyhow!("proof is pending, import block zero first"))
+            } else {
+                check_block_zero(&manifest.block_zero)
+            },
+        },
+        ManifestCheck {
+            name: "signed autopay transactions",
+            result: check_autopay_signed(&manifest, owner),
+        },
+        ManifestCheck {
+            name: "routable ip address",
+            result: check_ip(&manifest),
+        },
+    ])
+}
+
+/// Manifests validated at once in a parallel batch, each VDF verification uses a core
+pub const DEFAULT_VERIFY_JOBS: usize = 4;
+
+/// Outcome of validating one manifest of a batch
+pub type BatchResult = (PathBuf, Result<Vec<ManifestCheck>, Error>);
+
+/// The *.json files of a directory of manifests, sorted
+pub fn manifests_in_dir(dir: &PathBuf) -> Result<Vec<PathBuf>, Error> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("could not read manifest directory {:?}", dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Validates the manifests on up to jobs threads. Results are in the order of the paths.
+pub fn validate_manifests(paths: Vec<PathBuf>, jobs: usize) -> Vec<BatchResult> {
+    let count = paths.len();
+    let queue = Arc::new(Mutex::new(paths.into_iter().enumerate().collect::<VecDeque<_>>()));
+    let results = Arc::new(Mutex::new(Vec::with_capacity(count)));
+    let workers: Vec<_> = (0..jobs.max(1).min(count.max(1)))
+        .map(|_| {
+            let queue = queue.clone();
+            let results = results.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().pop_front();
+                match next {
+                    Some((i, path)) => {
+                        let checks = validate_manifest(&path);
+                        results.lock().unwrap().push((i, (path, checks)));
+                    }
+                    None => break,
+                }
+            })
+        })
+        .collect();
+    workers.into_iter().for_each(|w| w.join().expect("manifest validation thread panicked"));
+
+    let mut results = Arc::try_unwrap(results)
+        .ok()
+        .expect("validation threads are done")
+        .into_inner()
+        .unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Prints a pass/fail line for each check, returns true if all passed
+pub fn print_report(path: &PathBuf, checks: &[ManifestCheck]) -> bool {
+    println!("Manifest {:?}", path);
+    checks.iter().for_each(|c| match &c.result {
+        Ok(_) => println!("PASS: {}", c.name),
+        Err(e) => println!("FAIL: {}, {}", c.name, e),
+    });
+    checks.iter().all(|c| c.result.is_ok())
+}
+
+fn check_block_zero(block: &Block) -> Result<(), Error> {
+    if block.height != 0 {
+        bail!("block height is {}, expected 0", block.height);
+    }
+    if !miner::delay::verify(&block.preimage, &block.proof) {
+        bail!("proof does not verify against the preimage");
+    }
+    Ok(())
+}
 
+fn check_autopay_signed(manifest: &ValConfigs, owner: AccountAddress) -> Result<(), Error> {
+    let signed = match &manifest.autopay_signed {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+    let instructions = manifest.autopay_instructions.clone().unwrap_or_default();
+    if signed.len() != instructions.len() {
+        bail!(
+            "{} autopay instructions but {} signed transactions",
+            instructions.len(),
+            signed.len()
+        );
+    }
+    for (i, (tx, instr)) in signed.iter().zip(instructions.iter()).enumerate() {
+        if tx.sender() != owner {
+            bail!("tx {} is sent from {}, not from the account {}", i, tx.sender(), owner);
+        }
+        if let Err(e) = tx.clone().check_signature() {
+            bail!("tx {} has an invalid signature, {}", i, e);
+        }
+        instr
+            .check_instruction_match_payload(tx.payload())
+            .with_context(|| format!("tx {} doesn't match autopay instruction {:?}", i, instr.uid))?;
+    }
+    Ok(())
+}
+
+fn check_ip(manifest: &ValConfigs) -> Result<(), Error> {
+    match manifest.op_fullnode_network_addresses_string.find_ip_addr() {
+        Some(IpAddr::V4(addr)) if ip::is_public(&addr) => Ok(()),
+        Some(IpAddr::V6(_)) => Ok(()),
+        Some(addr) => bail!("{} is not reachable by peers", addr),
+        None => bail!("no ip address in the fullnode network address"),
+    }
+}
+
+/// Number of times to try a registration endpoint before giving up
+pub const REPORT_ATTEMPTS: u8 = 3;
+
+/// POSTs an account.json to a registration endpoint. Server errors are retried,
+/// returns the status and body of the final response.
+pub fn report_manifest(url: &Url, path: &PathBuf) -> Result<(StatusCode, String), Error> {
+    let body = fs::read_to_string(path)
+        .with_context(|| format!("could not read manifest {:?}", path))?;
+    let client = reqwest::blocking::Client::new();
+    let mut attempt = 1;
+    loop {
+        let res = client
+            .post(url.clone())
+            .header("Content-Type", "application/json")
+            .body(body.clone())
+            .send();
+        match res {
+            Ok(r) if r.status().is_server_error() && attempt < REPORT_ATTEMPTS => {
+                println!("WARN: {} responded {}, retrying.", url, r.status());
+            }
+            Ok(r) => {
+                let status = r.status();
+                return Ok((status, r.text().unwrap_or_default()));
+            }
+            Err(e) if attempt < REPORT_ATTEMPTS => {
+                println!("WARN: could not reach {}, retrying. Message: {:?}", url, e);
+            }
+            Err(e) => return Err(Error::new(e).context(format!("could not reach {}", url))),
+        }
+        attempt += 1;
+        thread::sleep(Duration::from_secs(2));
+    }
+}
+
+/// A field which differs between two manifests
+pub struct FieldDiff {
+    /// name of the field
+    pub field: &'static str,
+    /// value in the first manifest
+    pub a: String,
+    /// value in the second manifest
+    pub b: String,
+}
+
+/// Compares two manifests field by field, returns the fields which differ.
+/// A pending proof or missing autopay shows as "none".
+pub fn diff_manifests(a: &ValConfigs, b: &ValConfigs) -> Vec<FieldDiff> {
+    let fields: [(&'static str, fn(&ValConfigs) -> String); 11] = [
+        ("owner account", |m| m.ow_human_name.clone()),
+        ("operator account", |m| m.op_address.clone()),
+        ("operator auth key prefix", |m| hex::encode(&m.op_auth_key_prefix)),
+        ("consensus pubkey", |m| hex::encode(&m.op_consensus_pubkey)),
+        ("validator network addresses", |m| hex::encode(&m.op_validator_network_addresses)),
+        ("fullnode network address", |m| m.op_fullnode_network_addresses_string.to_string()),
+        ("operator name", |m| m.op_human_name.clone()),
+        ("block zero preimage", |m| block_field(m, |b| hex::encode(&b.preimage))),
+        ("block zero proof", |m| block_field(m, |b| hex::encode(&b.proof))),
+        ("autopay instructions", |m| match &m.autopay_instructions {
+            Some(i) => i.iter().map(|i| i.text_instruction()).collect::<Vec<_>>().join("; "),
+            None => "none".to_owned(),
+        }),
+        ("autopay signed transactions", |m| match &m.autopay_signed {
+            Some(s) => serde_json::to_string(s).unwrap_or_default(),
+            None => "none".to_owned(),
+        }),
+    ];
+
+    fields
+        .iter()
+        .map(|(field, value)| FieldDiff { field: *field, a: value(a), b: value(b) })
+        .filter(|d| d.a != d.b)
+        .collect()
+}
+
+fn block_field(m: &ValConfigs, value: fn(&Block) -> String) -> String {
+    if m.proof_pending {
+        "none".to_owned()
+    } else {
+        value(&m.block_zero)
+    }
+}
+
+/// Prints the differing fields, long values are shortened
+pub fn print_diff(a: &PathBuf, b: &PathBuf, diffs: &[FieldDiff]) {
+    println!("Comparing {:?} with {:?}", a, b);
+    if diffs.is_empty() {
+        println!("The manifests are the same");
+    }
+    diffs.iter().for_each(|d| {
+        println!("DIFF: {}\n  < {}\n  > {}", d.field, shorten(&d.a), shorten(&d.b));
+    });
+}
+
+fn shorten(value: &str) -> String {
+    if value.chars().count() > 64 {
+        format!("{}... ({} chars)", value.chars().take(64).collect::<String>(), value.chars().count())
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Prints what a funder needs from a manifest: the account, its ip, and what autopay commits
+pub fn print_summary(manifest: &ValConfigs) {
+    println!("account: {}", manifest.ow_human_name);
+    match manifest.op_fullnode_network_addresses_string.find_ip_addr() {
+        Some(addr) => println!("ip: {}", addr),
+        None => println!("ip: none in the fullnode network address"),
+    }
+    println!("block zero: {}", if manifest.proof_pending { "pending" } else { "included" });
+
+    match &manifest.autopay_instructions {
+        Some(instr) if !instr.is_empty() => {
+            let (balance, change) = PayInstruction::percent_totals(instr);
+            println!(
+                "autopay: {} instructions, {}% of balance and {}% of change",
+                instr.len(),
+                balance,
+                change
+            );
+            instr.iter().for_each(|i| println!("  {}", i.text_instruction()));
+        }
+        _ => println!("autopay: none"),
+    }
+    let signed = manifest.autopay_signed.as_ref().map_or(0, |s| s.len());
+    println!("signed autopay txs: {}", signed);
+}
+
+/// Newest template schema this binary can parse. Templates without a schema_version are version 1.
+pub const TEMPLATE_SCHEMA_VERSION: u64 = 1;
+
+/// Fields of a template the wizard reads
+const TEMPLATE_REQUIRED_FIELDS: [&str; 1] = ["autopay_instructions"];
+
+/// Checks a downloaded template can be parsed by this binary, before it's used to configure the account.
+/// A newer template names the onboard version it was made for in tool_version, if the publisher set it.
+pub fn check_template_schema(path: &PathBuf) -> Result<(), Error> {
+    let text = fs::read_to_string(path).with_context(|| format!("could not read template {:?}", path))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&text).with_context(|| format!("template {:?} is not valid json", path))?;
+
+    let version = json.get("schema_version").and_then(|v| v.as_u64()).unwrap_or(1);
+    if version > TEMPLATE_SCHEMA_VERSION {
+        let tool = json
+            .get("tool_version")
+            .and_then(|v| v.as_str())
+            .map(|v| format!(", made with onboard {}", v))
+            .unwrap_or_default();
+        bail!(
+            "template has schema version {}{}, but this onboard {} only reads up to version {}. Install a newer onboard to use it",
+            version,
+            tool,
+            env!("CARGO_PKG_VERSION"),
+            TEMPLATE_SCHEMA_VERSION
+        );
+    }
+
+    let missing: Vec<&str> = TEMPLATE_REQUIRED_FIELDS
+        .iter()
+        .filter(|f| json.get(**f).is_none())
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        bail!("template {:?} is missing the fields: {}", path, missing.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Modes of the files the wizard writes, which with a lax umask would be world-readable
+
+use anyhow::{bail, Context, Error};
+use std::{fs::File, path::{Path, PathBuf}};
+
+/// Mode of the files with key material
+pub const PRIVATE_MODE: u32 = 0o600;
+
+/// Mode of the configs and public manifests
+pub const PUBLIC_MODE: u32 = 0o644;
+
+/// Parses an octal mode, e.g. 640 or 0o640
+pub fn parse_file_mode(mode: &str) -> Result<u32, Error> {
+    let digits = mode.trim_start_matches("0o");
+    let parsed = u32::from_str_radix(digits, 8)
+        .with_context(|| format!("file mode {} is not octal, e.g. 640", mode))?;
+    if parsed > 0o777 {
+        bail!("file mode {} has bits above 777", mode);
+    }
+    Ok(parsed)
+}
+
+/// Sets the modes of the files which exist, PRIVATE_MODE for the ones with key material and
+/// PUBLIC_MODE for the others. The override is the mode of all of them.
+pub fn set_modes(files: &[(PathBuf, bool)], mode_override: Option<u32>) -> Result<(), Error> {
+    for (path, private) in files.iter().filter(|(p, _)| p.exists()) {
+        let mode = mode_override.unwrap_or(if *private { PRIVATE_MODE } else { PUBLIC_MODE });
+        set_mode(path, mode).with_context(|| format!("could not set the mode of {:?}", path))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(path: &PathBuf, mode: u32) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+/// Only unix has modes
+#[cfg(not(unix))]
+fn set_mode(_path: &PathBuf, _mode: u32) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Creates, or truncates, a file which is PRIVATE_MODE before anything is written to it
+#[cfg(unix)]
+pub fn create_private(path: &Path) -> Result<File, Error> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(PRIVATE_MODE)
+        .open(path)?;
+    // the mode only applies to a new file, an existing one keeps its own
+    file.set_permissions(std::fs::Permissions::from_mode(PRIVATE_MODE))?;
+    Ok(file)
+}
+
+/// Only unix has modes
+#[cfg(not(unix))]
+pub fn create_private(path: &Path) -> Result<File, Error> {
+    Ok(File::create(path)?)
+}
+
+#[test]
+fn test_parse_file_mode() {
+    assert_eq!(parse_file_mode("640").unwrap(), 0o640);
+    assert_eq!(parse_file_mode("0o600").unwrap(), PRIVATE_MODE);
+    assert_eq!(parse_file_mode("777").unwrap(), 0o777);
+    // setuid, setgid and sticky bits aren't modes of the files
+    assert!(parse_file_mode("1777").is_err());
+    assert!(parse_file_mode("4755").is_err());
+    assert!(parse_file_mode("0o2640").is_err());
+    assert!(parse_file_mode("648").is_err());
+    assert!(parse_file_mode("rw-r--r--").is_err());
+}
@@ -0,0 +1,368 @@
+//! Checks of the host before the wizard configures the node
+
+use anyhow::{bail, Error};
+use dialoguer::Confirm;
+use diem_types::{
+    account_address::AccountAddress,
+    chain_id::{ChainId, NamedChain},
+    transaction::authenticator::AuthenticationKey,
+    waypoint::Waypoint,
+};
+use ol::node::{client::make_client, node::NODE_PROCESS};
+use ol_types::pay_instruction::PayInstruction;
+use reqwest::Url;
+use std::{
+    fmt, fs,
+    net::TcpStream,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use sysinfo::{DiskExt, Pid, ProcessExt, ProcessorExt, System, SystemExt};
+
+/// Default tolerated difference between the local clock and the chain, in seconds
+pub const DEFAULT_MAX_CLOCK_SKEW_SECS: u64 = 30;
+
+/// Seconds to wait for a peer to accept a connection, when looking for a reachable one
+pub const PEER_CONNECT_TIMEOUT_SECS: u64 = 3;
+
+/// Default free disk space required on the node home's filesystem, in bytes
+pub const DEFAULT_MIN_FREE_DISK: u64 = 1_000_000_000;
+
+/// Pidfile of the node in the node home, e.g. the PIDFile of a systemd unit
+pub const NODE_PID_FILE: &str = "diem-node.pid";
+
+/// Difference in seconds between the local clock and the latest ledger timestamp of the upstream node
+pub fn clock_skew(upstream: &Url, waypoint: Waypoint) -> Result<u64, Error> {
+    let client = make_client(Some(upstream.to_owned()), waypoint)?;
+    let chain_usecs = client.get_metadata()?.timestamp;
+    let local_usecs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros() as u64;
+    let skew_usecs = if local_usecs > chain_usecs {
+        local_usecs - chain_usecs
+    } else {
+        chain_usecs - local_usecs
+    };
+    Ok(skew_usecs / 1_000_000)
+}
+
+/// Errors if the local clock is skewed more than max_skew_secs from the chain.
+/// Only warns if the upstream node can't be queried.
+pub fn check_time_sync(upstream: &Url, waypoint: Waypoint, max_skew_secs: u64) -> Result<(), Error> {
+    match clock_skew(upstream, waypoint) {
+        Ok(skew) if skew > max_skew_secs => bail!(
+            "local clock differs from the chain by {} seconds, the maximum is {}. Transactions would expire with the wrong timestamps. Sync the clock (e.g. with NTP) and run again",
+            skew,
+            max_skew_secs
+        ),
+        Ok(skew) => println!("Clock in sync with upstream peer, skew of {} seconds", skew),
+        Err(e) => println!("WARN: could not check clock sync with upstream peer, message: {:?}", e),
+    }
+    Ok(())
+}
+
+/// The first of the peers which accepts a connection on the port of its URL
+pub fn first_reachable_peer(peers: &[Url]) -> Option<Url> {
+    let timeout = Duration::from_secs(PEER_CONNECT_TIMEOUT_SECS);
+    peers
+        .iter()
+        .find(|peer| {
+            let reachable = peer
+                .socket_addrs(|| None)
+                .map(|addrs| addrs.iter().any(|a| TcpStream::connect_timeout(a, timeout).is_ok()))
+                .unwrap_or(false);
+            if !reachable {
+                println!("WARN: peer {} is not reachable", peer);
+            }
+            reachable
+        })
+        .cloned()
+}
+
+/// Free bytes on the filesystem holding the path, None if the disk can't be found
+pub fn free_disk_space(path: &Path) -> Option<u64> {
+    // the node home may not exist yet, use the closest directory that does
+    let existing = path.ancestors().find(|p| p.exists())?.canonicalize().ok()?;
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    sys.get_disks()
+        .iter()
+        .filter(|d| existing.starts_with(d.get_mount_point()))
+        .max_by_key(|d| d.get_mount_point().as_os_str().len())
+        .map(|d| d.get_available_space())
+}
+
+/// Errors if there are less than min_bytes free on the filesystem of the path.
+pub fn check_free_disk(path: &Path, min_bytes: u64) -> Result<(), Error> {
+    match free_disk_space(path) {
+        Some(free) if free < min_bytes => bail!(
+            "only {} MB are free on the disk of {:?}, at least {} MB are needed. Free up space or lower --min-free-disk",
+            free / 1_000_000,
+            path,
+            min_bytes / 1_000_000
+        ),
+        Some(free) => println!("Disk space OK, {} MB free", free / 1_000_000),
+        None => println!("WARN: could not check free disk space of {:?}", path),
+    }
+    Ok(())
+}
+
+/// Effective uid of the process
+#[cfg(unix)]
+#[allow(unsafe_code)]
+pub fn effective_uid() -> Option<u32> {
+    // geteuid always succeeds and has no side effects
+    Some(unsafe { libc::geteuid() })
+}
+
+/// Effective uid of the process, which only unix has
+#[cfg(not(unix))]
+pub fn effective_uid() -> Option<u32> {
+    None
+}
+
+/// Warns when running as root, since the key store would be owned by root, and a node running
+/// as a service user could not read it. Continues with allow_root, or if the operator confirms.
+pub fn check_not_root(allow_root: bool) -> Result<(), Error> {
+    if effective_uid() != Some(0) {
+        return Ok(());
+    }
+    println!(
+        "WARN: the wizard is running as root, so key_store.json and the configs will be owned by root. \
+         A node running as a service user won't be able to read them. Run the wizard as the user the node runs as."
+    );
+    if allow_root {
+        return Ok(());
+    }
+    match Confirm::new().with_prompt("Continue as root?").interact() {
+        Ok(true) => Ok(()),
+        Ok(false) => bail!("not running as root, run the wizard as the service user"),
+        Err(_) => bail!("running as root without a terminal to confirm, pass --allow-root to continue"),
+    }
+}
+
+/// CPU and memory of the host, to compare mining times across machines in support reports
+#[derive(Debug)]
+pub struct Hardware {
+    /// model of the CPU, as the OS reports it
+    pub cpu_brand: String,
+    /// logical cores
+    pub cores: usize,
+    /// total RAM in MB
+    pub total_ram_mb: u64,
+}
+
+impl Hardware {
+    /// The hardware of this host
+    pub fn detect() -> Self {
+        let mut sys = System::new();
+        sys.refresh_cpu();
+        sys.refresh_memory();
+        let processors = sys.get_processors();
+        Hardware {
+            cpu_brand: processors
+                .first()
+                .map(|p| p.get_brand().trim().to_owned())
+                .filter(|b| !b.is_empty())
+                .unwrap_or_else(|| "unknown CPU".to_owned()),
+            cores: processors.len(),
+            // sysinfo counts memory in KB
+            total_ram_mb: sys.get_total_memory() / 1_000,
+        }
+    }
+}
+
+impl fmt::Display for Hardware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {} cores, {} MB of RAM", self.cpu_brand, self.cores, self.total_ram_mb)
+    }
+}
+
+/// A diem-node using the node home, and how it was found
+#[derive(Debug)]
+pub struct RunningNode {
+    /// process id of the node
+    pub pid: Pid,
+    /// what points the node at the home: the pidfile, its command line, or its working directory
+    pub found_by: String,
+}
+
+/// The diem-node running against the home, if any. The node locks its DB with fcntl, which
+/// can't be checked without unsafe code, so the pidfile and the processes are checked instead.
+pub fn running_node(home: &Path) -> Option<RunningNode> {
+    let mut sys = System::new();
+    sys.refresh_processes();
+    let is_node = |pid: Pid| sys.get_process(pid).map_or(false, |p| p.name() == NODE_PROCESS);
+
+    let pid_file = home.join(NODE_PID_FILE);
+    if let Some(pid) = fs::read_to_string(&pid_file).ok().and_then(|t| t.trim().parse::<Pid>().ok()) {
+        // a stale pidfile of a stopped node is ignored
+        if is_node(pid) {
+            return Some(RunningNode {
+                pid,
+                found_by: format!("the pidfile {:?}", pid_file),
+            });
+        }
+    }
+
+    let home = home.canonicalize().unwrap_or_else(|_| home.to_owned());
+    let home_str = home.to_string_lossy().to_string();
+    sys.get_processes()
+        .iter()
+        .filter(|(_, p)| p.name() == NODE_PROCESS)
+        .find_map(|(pid, p)| {
+            if p.cmd().iter().any(|arg| arg.contains(&home_str)) {
+                Some(RunningNode { pid: *pid, found_by: "its command line".to_owned() })
+            } else if p.cwd().starts_with(&home) {
+                Some(RunningNode { pid: *pid, found_by: "its working directory".to_owned() })
+            } else {
+                None
+            }
+        })
+}
+
+/// Stops before rewriting the configs and keys of a home a node is running against, which
+/// could corrupt its state. Only warns with force.
+pub fn check_no_running_node(home: &Path, force: bool) -> Result<(), Error> {
+    let node = match running_node(home) {
+        Some(n) => n,
+        None => return Ok(()),
+    };
+    if force {
+        println!(
+            "WARN: a diem-node (pid {}) is running against {:?}, found by {}. Continuing with --force, restart the node after the wizard.",
+            node.pid, home, node.found_by
+        );
+        return Ok(());
+    }
+    bail!(
+        "a diem-node (pid {}) is running against {:?}, found by {}. Stop the node first (e.g. `systemctl stop diem-node`), or pass --force to configure it anyway",
+        node.pid,
+        home,
+        node.found_by
+    )
+}
+
+/// Name of the network for a chain id, None if it isn't a known network
+pub fn network_name(chain_id: u8) -> Option<String> {
+    NamedChain::from_chain_id(&ChainId::new(chain_id))
+        .ok()
+        .map(|n| n.to_string())
+}
+
+/// Errors on a chain id which isn't a known network, unless allow_unknown is set.
+/// Prints the network name for known ones.
+pub fn check_chain_id(chain_id: u8, allow_unknown: bool) -> Result<(), Error> {
+    match network_name(chain_id) {
+        Some(name) => println!("Onboarding to chain id {}, network {}", chain_id, name),
+        None if allow_unknown => println!(
+            "WARN: chain id {} is not a known network, continuing since --allow-unknown-chain is set",
+            chain_id
+        ),
+        None => bail!(
+            "chain id {} is not a known network, check the id or pass --allow-unknown-chain",
+            chain_id
+        ),
+    }
+    Ok(())
+}
+
+/// VDF difficulty the network of the chain id accepts for block zero, None if it isn't known
+pub fn expected_difficulty(chain_id: u8) -> Option<u64> {
+    match NamedChain::from_chain_id(&ChainId::new(chain_id)).ok()? {
+        NamedChain::MAINNET => Some(5_000_000),
+        NamedChain::TESTING => Some(100),
+        _ => None,
+    }
+}
+
+/// Errors if the difficulty the miner will use isn't the one the network accepts,
+/// since the proof would be rejected when the account is created.
+pub fn check_difficulty(chain_id: u8, difficulty: u64) -> Result<(), Error> {
+    match expected_difficulty(chain_id) {
+        Some(expected) if expected != difficulty => bail!(
+            "the miner will use difficulty {}, but chain id {} expects {}. Check NODE_ENV, or update onboard",
+            difficulty,
+            chain_id,
+            expected
+        ),
+        Some(_) => Ok(()),
+        None => {
+            println!("WARN: no known difficulty for chain id {}, mining with {}", chain_id, difficulty);
+            Ok(())
+        }
+    }
+}
+
+/// Auth key the account has on the upstream node, None if the account doesn't exist yet
+pub fn registered_auth_key(
+    upstream: &Url,
+    waypoint: Waypoint,
+    account: AccountAddress,
+) -> Result<Option<Vec<u8>>, Error> {
+    let client = make_client(Some(upstream.to_owned()), waypoint)?;
+    Ok(client
+        .get_account(&account)?
+        .map(|view| view.authentication_key.inner().to_vec()))
+}
+
+/// Errors if the operator account is already registered on chain with another key than
+/// the one about to be written to the key store. Only warns if the upstream node can't be queried.
+pub fn check_operator_account(
+    upstream: &Url,
+    waypoint: Waypoint,
+    operator: AccountAddress,
+    auth_key: AuthenticationKey,
+) -> Result<(), Error> {
+    match registered_auth_key(upstream, waypoint, operator) {
+        Ok(Some(registered)) if registered != auth_key.to_vec() => bail!(
+            "operator account {} is already registered on chain with auth key {}, which differs from the key being written {}. The key store would conflict with the on-chain operator, check the mnemonic and --account-index",
+            operator,
+            hex::encode(&registered),
+            auth_key
+        ),
+        Ok(Some(_)) => println!("Operator account {} already on chain with the same keys", operator),
+        Ok(None) => println!("Operator account {} not yet on chain", operator),
+        Err(e) => println!("WARN: could not check the operator account on the upstream peer, message: {:?}", e),
+    }
+    Ok(())
+}
+
+/// Destinations of the instructions which are not accounts on the upstream node, in order and without repeats
+pub fn unknown_destinations(
+    upstream: &Url,
+    waypoint: Waypoint,
+    instructions: &[PayInstruction],
+) -> Result<Vec<AccountAddress>, Error> {
+    let client = make_client(Some(upstream.to_owned()), waypoint)?;
+    let mut unknown = vec![];
+    for i in instructions {
+        if unknown.contains(&i.destination) {
+            continue;
+        }
+        if client.get_account(&i.destination)?.is_none() {
+            unknown.push(i.destination);
+        }
+    }
+    Ok(unknown)
+}
+
+/// Warns about autopay instructions paying to accounts which don't exist on chain, likely typos
+pub fn check_autopay_destinations(upstream: &Url, waypoint: Waypoint, instructions: &[PayInstruction]) {
+    match unknown_destinations(upstream, waypoint, instructions) {
+        Ok(unknown) if unknown.is_empty() => println!("All autopay destinations are accounts on chain"),
+        Ok(unknown) => {
+            for dest in unknown {
+                let uids: Vec<String> = instructions
+                    .iter()
+                    .filter(|i| i.destination == dest)
+                    .map(|i| i.uid.map_or("?".to_owned(), |u| u.to_string()))
+                    .collect();
+                println!(
+                    "WARN: autopay destination {} is not an account on chain, check instructions {} for a typo",
+                    dest,
+                    uids.join(", ")
+                );
+            }
+        }
+        Err(e) => println!("WARN: could not check the autopay destinations on the upstream peer, message: {:?}", e),
+    }
+}
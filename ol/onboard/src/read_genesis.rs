@@ -1,8 +1,9 @@
   //! read genesis
 
-use std::{fs::File, io::Read, path::PathBuf};
-use anyhow::Error;
-use diem_types::transaction::Transaction;
+use std::{fs::{self, File}, io::Read, path::PathBuf};
+use anyhow::{bail, Error};
+use diem_genesis_tool::waypoint::CreateWaypoint;
+use diem_types::{transaction::Transaction, waypoint::Waypoint};
   
 /// deserialize a genesis.blob in to a Transaction type
 pub fn gen_tx_from_blob(genesis_path: &PathBuf) -> Result<Transaction, Error> {
@@ -10,4 +11,25 @@ pub fn gen_tx_from_blob(genesis_path: &PathBuf) -> Result<Transaction, Error> {
     let mut buffer = vec![];
     file.read_to_end(&mut buffer)?;
     Ok(bcs::from_bytes(&buffer)?)
-  }
\ No newline at end of file
+  }
+
+/// the genesis_waypoint file written next to a downloaded or bundled genesis.blob, if any
+pub fn expected_waypoint(genesis_path: &PathBuf) -> Result<Option<Waypoint>, Error> {
+    let path = genesis_path.with_file_name("genesis_waypoint");
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(&path)?.trim().parse()?))
+}
+
+/// Executes the genesis transaction on a temporary db to recompute its waypoint, and
+/// compares it to the expected one. Returns the computed waypoint.
+pub fn verify_genesis(genesis_path: &PathBuf, expected: Option<Waypoint>) -> Result<Waypoint, Error> {
+    let computed = CreateWaypoint::extract_waypoint(gen_tx_from_blob(genesis_path)?)?;
+    if let Some(w) = expected {
+        if w != computed {
+            bail!("genesis {:?} has waypoint {}, but {} was expected", genesis_path, computed, w);
+        }
+    }
+    Ok(computed)
+}
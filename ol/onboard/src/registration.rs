@@ -0,0 +1,162 @@
+//! Genesis registration bundle, which a ceremony participant uploads to the coordinator:
+//! the account.json, the block zero and the public network keys, in one signed zip
+
+use anyhow::{bail, Context, Error};
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    x25519, HashValue, Signature, SigningKey, ValidCryptoMaterialStringExt,
+};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_types::account_address::AccountAddress;
+use ol_keys::scheme::KeyScheme;
+use ol_types::{account::ValConfigs, block::Block};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::chain_info::SIGNATURE_EXTENSION;
+
+/// Extension of the bundle, after the account address
+pub const BUNDLE_EXTENSION: &str = "registration.zip";
+
+/// Name of the public keys in the bundle
+pub const NETWORK_KEYS_FILE: &str = "network_keys.json";
+
+/// Public keys of the node, which the coordinator checks the manifest against
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetworkKeys {
+    /// owner account
+    pub account: AccountAddress,
+    /// owner key, which signs the bundle
+    pub owner_public_key: Ed25519PublicKey,
+    /// consensus key
+    pub consensus_public_key: Ed25519PublicKey,
+    /// x25519 identity of the validator network
+    pub validator_network_public_key: x25519::PublicKey,
+    /// x25519 identity of the fullnode network
+    pub fullnode_network_public_key: x25519::PublicKey,
+}
+
+impl NetworkKeys {
+    /// Public keys of the key scheme
+    pub fn new(keys: &KeyScheme) -> Result<Self, Error> {
+        let network_key = |k: Ed25519PublicKey| {
+            x25519::PublicKey::from_ed25519_public_bytes(&k.to_bytes())
+                .map_err(|e| Error::msg(format!("not a network key: {:?}", e)))
+        };
+        Ok(NetworkKeys {
+            account: keys.derived_address(),
+            owner_public_key: keys.child_0_owner.get_public(),
+            consensus_public_key: keys.child_4_consensus.get_public(),
+            validator_network_public_key: network_key(keys.child_2_val_network.get_public())?,
+            fullnode_network_public_key: network_key(keys.child_3_fullnode_network.get_public())?,
+        })
+    }
+}
+
+/// What the owner key signs: the account and the sha3 of the zip
+#[derive(Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+pub struct BundleDigest {
+    /// owner account
+    pub account: AccountAddress,
+    /// sha3 of the zip bytes
+    pub bundle_sha3: HashValue,
+}
+
+/// Checks the files of the bundle describe the same validator
+pub fn check_bundle(manifest: &ValConfigs, block: &Block, keys: &NetworkKeys) -> Result<(), Error> {
+    if manifest.proof_pending {
+        bail!("the block zero of the manifest is still pending, mine it before registering");
+    }
+    if manifest.block_zero.preimage != block.preimage || manifest.block_zero.proof != block.proof {
+        bail!("the block zero of the manifest is not the block_0.json of the node");
+    }
+    if manifest.ow_human_name != keys.account.to_string() {
+        bail!(
+            "the manifest is of account {}, the mnemonic of {}",
+            manifest.ow_human_name,
+            keys.account
+        );
+    }
+    if manifest.op_consensus_pubkey != keys.consensus_public_key.to_bytes().to_vec() {
+        bail!("the consensus key of the manifest is not the one of the mnemonic");
+    }
+    if manifest.op_fullnode_network_addresses_string.find_noise_proto()
+        != Some(keys.fullnode_network_public_key)
+    {
+        bail!("the fullnode address of the manifest is not for the fullnode network key of the mnemonic");
+    }
+    Ok(())
+}
+
+/// Zips the manifest, block zero and network keys into <account>.registration.zip in the
+/// output directory, after checking they match, and writes the owner's signature next to it.
+pub fn write_bundle(
+    manifest_path: &Path,
+    block_path: &Path,
+    keys: &KeyScheme,
+    out_dir: &Path,
+) -> Result<PathBuf, Error> {
+    let manifest_bytes = fs::read(manifest_path)
+        .with_context(|| format!("could not read manifest {:?}", manifest_path))?;
+    let manifest: ValConfigs = serde_json::from_slice(&manifest_bytes)
+        .with_context(|| format!("{:?} is not an account.json", manifest_path))?;
+    let block_bytes = fs::read(block_path)
+        .with_context(|| format!("could not read block zero {:?}", block_path))?;
+    let block: Block = serde_json::from_slice(&block_bytes)
+        .with_context(|| format!("{:?} is not a block", block_path))?;
+    let network_keys = NetworkKeys::new(keys)?;
+    check_bundle(&manifest, &block, &network_keys)?;
+
+    let path = out_dir.join(format!("{}.{}", network_keys.account, BUNDLE_EXTENSION));
+    let mut zip = ZipWriter::new(
+        File::create(&path).with_context(|| format!("could not create bundle {:?}", &path))?,
+    );
+    let files = vec![
+        ("account.json", manifest_bytes),
+        ("block_0.json", block_bytes),
+        (NETWORK_KEYS_FILE, serde_json::to_vec_pretty(&network_keys)?),
+    ];
+    for (name, bytes) in files {
+        zip.start_file(name, FileOptions::default())?;
+        zip.write_all(&bytes)?;
+    }
+    zip.finish()?;
+
+    let digest = BundleDigest {
+        account: network_keys.account,
+        bundle_sha3: HashValue::sha3_256_of(&fs::read(&path)?),
+    };
+    let signature = keys.child_0_owner.get_private_key().sign(&digest);
+    let sig_path = signature_path(&path);
+    fs::write(&sig_path, signature.to_encoded_string()?)
+        .with_context(|| format!("could not write signature {:?}", &sig_path))?;
+    Ok(path)
+}
+
+/// Path of the owner's signature of a bundle, next to it with .sig after its extension
+pub fn signature_path(path: &Path) -> PathBuf {
+    let sig = SIGNATURE_EXTENSION.trim_start_matches('.');
+    match path.extension() {
+        Some(ext) => path.with_extension(format!("{}.{}", ext.to_string_lossy(), sig)),
+        None => path.with_extension(sig),
+    }
+}
+
+/// Checks the signature of a bundle, as the coordinator would, with the owner key in it
+pub fn verify_bundle(path: &Path, account: AccountAddress, owner: &Ed25519PublicKey) -> Result<(), Error> {
+    let sig_path = signature_path(path);
+    let signature = Ed25519Signature::from_encoded_string(fs::read_to_string(&sig_path)?.trim())
+        .map_err(|e| Error::msg(format!("{:?} is not a hex ed25519 signature: {:?}", &sig_path, e)))?;
+    let digest = BundleDigest {
+        account,
+        bundle_sha3: HashValue::sha3_256_of(&fs::read(path)?),
+    };
+    signature
+        .verify(&digest, owner)
+        .context("the bundle is not signed by the owner key")
+}
@@ -0,0 +1,78 @@
+//! Status output of the wizards, which can be sent to a log file instead of the terminal
+//! so stdout only has machine readable output.
+//!
+//! The `status_ok!` and `status_info!` macros here take the same arguments as the
+//! abscissa ones, and fall back to them when no log file is set. The terminal they
+//! fall back to has no colors with --no-color or NO_COLOR, see `colors_enabled`.
+
+use anyhow::{Context, Error};
+use once_cell::sync::Lazy;
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Environment variable conventionally disabling ANSI colors, see no-color.org
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// Colors are on unless --no-color is passed or NO_COLOR is set to a non empty value
+pub fn colors_enabled(no_color_flag: bool) -> bool {
+    !no_color_flag && env::var(NO_COLOR_ENV).map(|v| v.is_empty()).unwrap_or(true)
+}
+
+/// The status log file, if one was set
+static STATUS_LOG: Lazy<Mutex<Option<File>>> = Lazy::new(|| Mutex::new(None));
+
+/// Sends status output to the file at path, appending to it
+pub fn set_log(path: &Path) -> Result<(), Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("could not open status log {:?}", path))?;
+    *STATUS_LOG.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+/// Writes a status line to the log file. Returns false if there is no log file,
+/// and the status should go to the terminal.
+pub fn write(level: &str, status: &str, msg: &str) -> bool {
+    match STATUS_LOG.lock().unwrap().as_mut() {
+        Some(file) => {
+            writeln!(file, "[{}] {} {}", level, status.trim(), msg.trim()).ok();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Status message of a completed step, to the status log or the terminal
+#[macro_export]
+macro_rules! status_ok {
+    ($status:expr, $msg:expr) => {{
+        let msg = $msg.to_string();
+        if !$crate::status::write("ok", $status, &msg) {
+            abscissa_core::status_ok!($status, "{}", msg);
+        }
+    }};
+    ($status:expr, $fmt:expr, $($arg:tt)+) => {
+        $crate::status_ok!($status, format!($fmt, $($arg)+))
+    };
+}
+
+/// Informational status message, to the status log or the terminal
+#[macro_export]
+macro_rules! status_info {
+    ($status:expr, $msg:expr) => {{
+        let msg = $msg.to_string();
+        if !$crate::status::write("info", $status, &msg) {
+            abscissa_core::status_info!($status, "{}", msg);
+        }
+    }};
+    ($status:expr, $fmt:expr, $($arg:tt)+) => {
+        $crate::status_info!($status, format!($fmt, $($arg)+))
+    };
+}
@@ -0,0 +1,61 @@
+//! Systemd unit for running the configured node as a service
+
+use anyhow::{Context, Error};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// File name of the unit written by the wizard
+pub const NODE_UNIT_FILE: &str = "0l-node.service";
+
+/// Where the node binary is installed by the 0L install scripts
+pub const DEFAULT_NODE_BIN: &str = "/usr/local/bin/diem-node";
+
+/// Contents of the unit running the validator node configured in the node home.
+/// The unit is aliased to diem-node.service, which is the service `ol` checks for.
+pub fn node_unit(home_path: &Path, node_bin: &Path, user: Option<&str>) -> String {
+    let user_line = match user {
+        Some(u) => format!("User={}\n", u),
+        None => "".to_owned(),
+    };
+    format!(
+        "[Unit]
+Description=0L Validator Node
+After=network-online.target
+Wants=network-online.target
+StartLimitIntervalSec=300
+StartLimitBurst=5
+
+[Service]
+{user}WorkingDirectory={home}
+ExecStart={bin} --config {config}
+Restart=on-failure
+RestartSec=10s
+LimitNOFILE=65536
+TimeoutStopSec=60
+
+[Install]
+WantedBy=multi-user.target
+Alias=diem-node.service
+",
+        user = user_line,
+        home = home_path.display(),
+        bin = node_bin.display(),
+        config = home_path.join("validator.node.yaml").display(),
+    )
+}
+
+/// Writes the node unit to the path, or into it if the path is a directory.
+/// The service runs as the current user unless that is root.
+pub fn write_node_unit(home_path: &Path, node_bin: &Path, out: &Path) -> Result<PathBuf, Error> {
+    let path = if out.is_dir() {
+        out.join(NODE_UNIT_FILE)
+    } else {
+        out.to_owned()
+    };
+    let user = env::var("USER").ok().filter(|u| u != "root");
+    fs::write(&path, node_unit(home_path, node_bin, user.as_deref()))
+        .with_context(|| format!("could not write systemd unit {:?}", &path))?;
+    Ok(path)
+}
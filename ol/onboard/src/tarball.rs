@@ -0,0 +1,55 @@
+//! Tarball of the files of a run, to move them from the machine the wizard ran on to the node
+
+use crate::permissions::{create_private, PRIVATE_MODE};
+use anyhow::{Context, Error};
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+use tar::{Builder, Header};
+
+/// A file of the tarball, and whether it has private keys
+#[derive(Debug)]
+pub struct TarballEntry {
+    /// file on disk
+    pub path: PathBuf,
+    /// name in the tarball
+    pub name: String,
+    /// stored with PRIVATE_MODE instead of the mode on disk
+    pub private: bool,
+}
+
+/// Writes the entries which exist to a .tar.gz, with their modes kept. The tarball is
+/// PRIVATE_MODE when an entry is private. Returns the names of the entries not found.
+pub fn write_tarball(out: &Path, entries: &[TarballEntry]) -> Result<Vec<String>, Error> {
+    // the keys are streamed in, so the tarball must be private before the first byte
+    let file = if entries.iter().any(|e| e.private) {
+        create_private(out)
+    } else {
+        File::create(out).map_err(Error::from)
+    }
+    .with_context(|| format!("could not create tarball {:?}", out))?;
+    let mut builder = Builder::new(GzEncoder::new(file, Compression::default()));
+    let mut missing = vec![];
+    for entry in entries {
+        if !entry.path.exists() {
+            missing.push(entry.name.clone());
+            continue;
+        }
+        let metadata = fs::metadata(&entry.path)?;
+        let mut header = Header::new_gnu();
+        header.set_metadata(&metadata);
+        if entry.private {
+            header.set_mode(PRIVATE_MODE);
+        }
+        header.set_cksum();
+        let data = File::open(&entry.path)
+            .with_context(|| format!("could not read {:?}", &entry.path))?;
+        builder
+            .append_data(&mut header, &entry.name, data)
+            .with_context(|| format!("could not add {:?} to the tarball", &entry.path))?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(missing)
+}
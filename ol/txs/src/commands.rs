@@ -11,7 +11,7 @@
 //! application's configuration file.
 
 mod create_account_cmd;
-mod create_validator_cmd;
+pub mod create_validator_cmd;
 mod oracle_upgrade_cmd;
 mod version_cmd;
 pub mod autopay_batch_cmd;
@@ -3,7 +3,7 @@
 #![allow(clippy::never_loop)]
 
 use abscissa_core::{Command, Options, Runnable};
-use anyhow::Error;
+use anyhow::{bail, Error};
 use diem_types::transaction::{SignedTransaction, TransactionPayload};
 use diem_transaction_builder::stdlib as transaction_builder;
 use ol::node::{node::Node};
@@ -13,20 +13,24 @@ use crate::{
     sign_tx::sign_tx, 
     submit_tx::{tx_params_wrapper, batch_wrapper, TxParams}
 };
-use dialoguer::Confirm;
+use dialoguer::{Confirm, Input};
 use std::{path::PathBuf, process::exit};
 use ol_types::{
     config::{TxType, IS_TEST}, 
     autopay::AutoPayResource, 
-    pay_instruction::PayInstruction
+    pay_instruction::{InstructionErrors, PayInstruction}
 };
 use diem_types::account_address::AccountAddress;
 
+pub use ol_types::pay_instruction::InstructionError;
+
 /// command to submit a batch of autopay tx from file
 #[derive(Command, Debug, Default, Options)]
 pub struct AutopayBatchCmd {
     #[options(short = "f", help = "path of autopay_batch_file.json")]
     autopay_batch_file: PathBuf,
+    #[options(help = "epochs to shift the instructions by, negative to start earlier")]
+    autopay_start_offset: Option<i64>,
 }
 
 impl Runnable for AutopayBatchCmd {
@@ -58,36 +62,62 @@ impl Runnable for AutopayBatchCmd {
         );
 
         let instructions = PayInstruction::parse_autopay_instructions(
-            &self.autopay_batch_file, Some(epoch), start_id
-        ).unwrap();
-        let scripts = process_instructions(instructions);
+            &self.autopay_batch_file, Some(epoch), start_id, self.autopay_start_offset
+        ).unwrap_or_else(|e| {
+            match e.downcast_ref::<InstructionErrors>() {
+                Some(errors) => print_instruction_errors(&errors.0),
+                None => println!("ERROR: could not parse the autopay instructions, exiting. Message: {:?}", e),
+            }
+            exit(1);
+        });
+        let scripts = process_instructions(instructions).unwrap_or_else(|errors| {
+            print_instruction_errors(&errors);
+            exit(1);
+        });
         batch_wrapper(scripts, &tx_params, entry_args.no_send, entry_args.save_path)
     }
 }
 
-/// Process autopay instructions into scripts
-pub fn process_instructions(instructions: Vec<PayInstruction>) -> Vec<TransactionPayload> {
-  // TODO: Check instruction IDs are sequential.
-  instructions.into_iter().filter_map(|i| {
-    // double check transactions
-      match i.type_move.unwrap()<= 3 {
-          true => {},
-          false => {
-            println!("Instruction type not valid for transactions: {:?}", &i);
-            exit(1); 
-          },
-      }
-      
-      if i.duration_epochs.is_some() && i.duration_epochs.unwrap() < 1 {
-        println!("Instructions must have epoch_duration greater than 0. Exiting. Instruction: {:?}", &i);
-        exit(1);
-      }
+/// Check every instruction, and collect all the problems found
+pub fn check_instructions(instructions: &[PayInstruction]) -> Vec<InstructionError> {
+  let mut errors = vec![];
+  instructions.iter().enumerate().for_each(|(index, i)| {
+    let mut fail = |reason: &str| errors.push(InstructionError {
+      index,
+      instruction: i.clone(),
+      reason: reason.to_owned(),
+    });
 
-      if i.end_epoch.is_none() || i.end_epoch.unwrap() < 1 {
-        println!("Instructions must have end_epoch greater than 0. Exiting. Instruction: {:?}", &i);
-        exit(1);
-      }
+    if i.uid.is_none() {
+      fail("instruction has no uid");
+    }
+    match i.type_move {
+      Some(t) if t <= 3 => {},
+      _ => fail("instruction type not valid for transactions"),
+    }
+    if i.value_move.is_none() {
+      fail("instruction has no value");
+    }
+    if i.duration_epochs.is_some() && i.duration_epochs.unwrap() < 1 {
+      fail("instructions must have epoch_duration greater than 0");
+    }
+    if i.end_epoch.is_none() || i.end_epoch.unwrap() < 1 {
+      fail("instructions must have end_epoch greater than 0");
+    }
+  });
+  errors
+}
 
+/// Process autopay instructions into scripts. All instructions are checked
+/// first, and every error is returned if any of them is invalid.
+pub fn process_instructions(
+  instructions: Vec<PayInstruction>
+) -> Result<Vec<TransactionPayload>, Vec<InstructionError>> {
+  // TODO: Check instruction IDs are sequential.
+  let errors = check_instructions(&instructions);
+  if !errors.is_empty() { return Err(errors) }
+
+  let confirmed = instructions.into_iter().filter_map(|i| {
       println!("{}", i.text_instruction());
       // accept if CI mode.
       if *IS_TEST { return Some(i) }            
@@ -100,7 +130,19 @@ pub fn process_instructions(instructions: Vec<PayInstruction>) -> Vec<Transactio
         }
       }            
   })
-  .map(|i| {
+  .collect();
+  encode_instructions(confirmed)
+}
+
+/// Encode autopay instructions into scripts without asking for confirmation.
+/// All instructions are checked first, and every error is returned if any of them is invalid.
+pub fn encode_instructions(
+  instructions: Vec<PayInstruction>
+) -> Result<Vec<TransactionPayload>, Vec<InstructionError>> {
+  let errors = check_instructions(&instructions);
+  if !errors.is_empty() { return Err(errors) }
+
+  let scripts = instructions.into_iter().map(|i| {
     transaction_builder::encode_autopay_create_instruction_script_function(
       i.uid.unwrap(), 
       i.type_move.unwrap(), 
@@ -109,7 +151,60 @@ pub fn process_instructions(instructions: Vec<PayInstruction>) -> Vec<Transactio
       i.value_move.unwrap()
     )
   })
-  .collect()
+  .collect();
+  Ok(scripts)
+}
+
+/// Show the instructions as a numbered list and let the operator remove any of them
+/// before confirming the whole set. Returns the instructions which were kept, or an error
+/// if the operator aborts or there is no terminal to ask on.
+pub fn review_instructions(mut instructions: Vec<PayInstruction>) -> Result<Vec<PayInstruction>, Error> {
+  let no_terminal = |e: std::io::Error| {
+    anyhow::anyhow!(
+      "could not ask to review the autopay instructions, pass --no-autopay-review to sign them without review: {}",
+      e
+    )
+  };
+  loop {
+    println!("\nAutopay instructions to be signed:");
+    instructions.iter().enumerate().for_each(|(n, i)| {
+      println!("{}: {}", n + 1, i.text_instruction());
+    });
+
+    let remove: String = Input::new()
+      .with_prompt("Numbers of instructions to remove, separated by commas (leave empty to keep all)")
+      .allow_empty(true)
+      .interact_text()
+      .map_err(no_terminal)?;
+
+    if remove.trim().is_empty() {
+      match Confirm::new().with_prompt("Sign all the instructions above?").interact().map_err(no_terminal)? {
+        true => return Ok(instructions),
+        _ => bail!("autopay configuration aborted. Check batch configuration file or template"),
+      }
+    }
+
+    let mut numbers: Vec<usize> = remove
+      .split(',')
+      .filter_map(|n| match n.trim().parse::<usize>() {
+        Ok(n) if (1..=instructions.len()).contains(&n) => Some(n),
+        _ => {
+          println!("WARN: ignoring {:?}, not a number in the list", n.trim());
+          None
+        }
+      })
+      .collect();
+    // remove from the back so the numbers in the list stay valid
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers.iter().rev().for_each(|n| { instructions.remove(n - 1); });
+  }
+}
+
+/// Print all the instruction errors
+pub fn print_instruction_errors(errors: &[InstructionError]) {
+  println!("Found {} problems in the autopay instructions:", errors.len());
+  errors.iter().for_each(|e| println!("{}", e));
 }
 
 /// return a vec of signed transactions
@@ -131,30 +226,80 @@ pub fn sign_instructions(
 fn test_instruction_script_match() {
   use diem_types::account_address::AccountAddress;
   use ol_types::pay_instruction::InstructionType;
-  if let TransactionPayload::Script(s) = transaction_builder::encode_autopay_create_instruction_script_function(
+  let payload = transaction_builder::encode_autopay_create_instruction_script_function(
       1, 
       0, 
       AccountAddress::ZERO, 
       10, 
       1000
-  ) {
-    let instr = PayInstruction {
-        uid: Some(1),
-        type_of: InstructionType::PercentOfBalance,
-        destination: AccountAddress::ZERO,
-        end_epoch: Some(10),
-        duration_epochs: None,
-        note: Some("test".to_owned()),
-        type_move: Some(0),
-        value: 10f64,
-        value_move: Some(1000u64),
-    };
-
-    instr.check_instruction_match_tx(s).unwrap();
-  }
+  );
+  let instr = PayInstruction {
+      uid: Some(1),
+      type_of: InstructionType::PercentOfBalance,
+      destination: AccountAddress::ZERO,
+      end_epoch: Some(10),
+      duration_epochs: None,
+      note: Some("test".to_owned()),
+      type_move: Some(0),
+      value: 10f64,
+      value_move: Some(1000u64),
+  };
+
+  instr.check_instruction_match_payload(&payload).unwrap();
+}
 
+#[test]
+fn test_signed_tx_mismatch() {
+  use diem_types::{account_address::AccountAddress, chain_id::ChainId};
+  use ol_types::pay_instruction::InstructionType;
+  let instr = PayInstruction {
+      uid: Some(1),
+      type_of: InstructionType::PercentOfBalance,
+      destination: AccountAddress::ZERO,
+      end_epoch: Some(10),
+      duration_epochs: None,
+      note: None,
+      type_move: Some(0),
+      value: 10f64,
+      value_move: Some(1000u64),
+  };
+  // signed with another end epoch than the instruction's
+  let payload = transaction_builder::encode_autopay_create_instruction_script_function(
+      1, 0, AccountAddress::ZERO, 20, 1000
+  );
+  let signed = sign_tx(payload, &TxParams::test_fixtures(), 0, ChainId::new(1)).unwrap();
+  assert!(instr.check_instruction_match_payload(signed.payload()).is_err());
 
+  // a payload which isn't autopay is a failed check too
+  let other = transaction_builder::encode_demo_e2e_script_function(42);
+  let signed = sign_tx(other, &TxParams::test_fixtures(), 1, ChainId::new(1)).unwrap();
+  assert!(instr.check_instruction_match_payload(signed.payload()).is_err());
+}
+
+#[test]
+fn test_check_instructions_collects_all_errors() {
+  use ol_types::pay_instruction::InstructionType;
+  let valid = PayInstruction {
+      uid: Some(1),
+      type_of: InstructionType::PercentOfBalance,
+      destination: AccountAddress::ZERO,
+      end_epoch: Some(10),
+      duration_epochs: None,
+      note: None,
+      type_move: Some(0),
+      value: 10f64,
+      value_move: Some(1000u64),
+  };
+  let mut no_end = valid.clone();
+  no_end.end_epoch = None;
+  let mut bad_type = valid.clone();
+  bad_type.type_move = Some(9);
+  bad_type.duration_epochs = Some(0);
 
+  let errors = check_instructions(&[valid, no_end, bad_type]);
+  assert_eq!(errors.len(), 3);
+  assert_eq!(errors[0].index, 1);
+  assert!(errors[1..].iter().all(|e| e.index == 2));
 }
 
 fn get_autopay_start_id(node: &mut Node, account: AccountAddress) -> Result<u64, Error>{
@@ -79,7 +79,7 @@ impl Runnable for CreateValidatorCmd {
         let new_account: ValConfigs =
             serde_json::from_reader(file).expect("file should be proper JSON");
         // submit initial autopay if there are any
-        match new_account.check_autopay() {
+        match new_account.check_autopay(true) {
             Ok(_) => {
                 println!("Sending account creation transaction");
                 maybe_submit(
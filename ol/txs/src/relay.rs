@@ -1,11 +1,14 @@
 //! `relay`
 
 #![forbid(unsafe_code)]
-use std::path::PathBuf;
+use std::{path::PathBuf, thread};
 
 use crate::{
   save_tx,
-  submit_tx::{tx_params_wrapper, wait_for_tx, TxParams},
+  submit_tx::{
+    classify_submit_error, eval_tx_status, executed_after_timeout, mempool_backoff,
+    tx_params_wrapper, wait_for_tx, wait_for_tx_timeout, SubmitBounce, SubmitPolicy, TxParams,
+  },
 };
 use anyhow::Error;
 use cli::diem_client::DiemClient;
@@ -62,6 +65,70 @@ pub fn relay_batch(batch_tx: &Vec<SignedTransaction>, tx_params: &TxParams) -> R
     Ok(())
 }
 
+/// Relays a signed tx with the retries of the policy, reporting each attempt. Since the tx
+/// is signed already, only a full mempool and timeouts are retried, not sequence number conflicts.
+/// A tx which timed out is looked up on chain before relaying it again.
+pub fn relay_tx_with_policy(
+  tx_params: &TxParams,
+  txn: &SignedTransaction,
+  policy: &SubmitPolicy,
+) -> Result<TransactionView, Error> {
+  let attempts = policy.retries + 1;
+  let mut attempt = 1;
+  loop {
+    let mut client = DiemClient::new(tx_params.url.to_owned(), tx_params.waypoint)?;
+    println!("Relaying tx {} of {}, attempt {} of {}", txn.sequence_number(), txn.sender(), attempt, attempts);
+    let bounce = match client.submit_transaction(txn) {
+      Ok(_) => match wait_for_tx_timeout(txn.sender(), txn.sequence_number(), &mut client, policy.timeout_secs) {
+        Some(res) => return eval_tx_status(res.clone()).map(|_| res),
+        // it may still have executed, and submitting again would bounce on its sequence number
+        None => match executed_after_timeout(txn, &client) {
+          Some(res) => return eval_tx_status(res.clone()).map(|_| res),
+          None => None,
+        },
+      },
+      Err(e) => Some(e),
+    };
+    if attempt == attempts {
+      let e = bounce.unwrap_or_else(|| Error::msg(format!("no tx status after {} seconds", policy.timeout_secs)));
+      return Err(e.context(format!("tx not executed after {} attempts", attempts)));
+    }
+    match bounce {
+      None => println!("Attempt {} timed out, retrying", attempt),
+      Some(e) => match classify_submit_error(&e) {
+        SubmitBounce::MempoolFull => {
+          let wait = mempool_backoff(attempt);
+          println!("Attempt {} failed, mempool full, retrying in {:?}", attempt, wait);
+          thread::sleep(wait);
+        }
+        SubmitBounce::SequenceNumber => {
+          return Err(e.context("sequence number of the signed tx is taken, it must be signed again"))
+        }
+        SubmitBounce::Other => return Err(e),
+      },
+    }
+    attempt += 1;
+  }
+}
+
+/// relay_batch with the retries of the policy for each tx, stopping at the first which fails
+pub fn relay_batch_with_policy(
+  batch_tx: &[SignedTransaction],
+  tx_params: &TxParams,
+  policy: &SubmitPolicy,
+) -> Result<(), Error> {
+  for (i, tx) in batch_tx.iter().enumerate() {
+    relay_tx_with_policy(tx_params, tx, policy).map_err(|e| {
+      e.context(format!(
+        "tx {} of the batch failed, the {} before it may have executed",
+        i + 1,
+        i
+      ))
+    })?;
+  }
+  Ok(())
+}
+
 /// submit transaction from a file with batch of signed transactions
 pub fn relay_from_file(path: PathBuf) -> Result<(), Error> {
   //NOTE: Cost does not affect relaying, that's determined in original tx
@@ -12,6 +12,7 @@ use cli::{diem_client::DiemClient, AccountData, AccountStatus};
 use ol_keys::{wallet, scheme::KeyScheme};
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    hash::CryptoHash,
     test_utils::KeyPair,
 };
 use diem_global_constants::OPERATOR_KEY;
@@ -20,7 +21,7 @@ use diem_secure_storage::{CryptoStorage, Namespaced, OnDiskStorage, Storage};
 use diem_types::{account_address::AccountAddress, waypoint::Waypoint};
 use diem_types::{
     chain_id::ChainId,
-    transaction::{authenticator::AuthenticationKey, SignedTransaction, TransactionPayload},
+    transaction::{authenticator::AuthenticationKey, SignedTransaction, Transaction, TransactionPayload},
 };
 
 use diem_wallet::WalletLibrary;
@@ -92,7 +93,7 @@ pub fn maybe_submit(
 ) -> Result<SignedTransaction, Error> {
     let mut client = DiemClient::new(tx_params.url.clone(), tx_params.waypoint).unwrap();
 
-    let (mut account_data, txn) = stage(script, tx_params, &mut client);
+    let (mut account_data, txn) = stage(script, tx_params, &mut client)?;
     if let Some(path) = save_path {
         // TODO: This will not work with batch operations like autopay_batch, last one will overwrite the file.
         save_tx(txn.clone(), path);
@@ -110,6 +111,140 @@ pub fn maybe_submit(
         Err(e) => Err(e),
     }
 }
+/// Retries of a submission, and how long each attempt waits for the tx to execute
+#[derive(Debug, Clone)]
+pub struct SubmitPolicy {
+    /// attempts after the first one
+    pub retries: u32,
+    /// seconds an attempt waits for the tx status
+    pub timeout_secs: u64,
+}
+
+impl Default for SubmitPolicy {
+    fn default() -> Self {
+        SubmitPolicy {
+            retries: 0,
+            timeout_secs: 60,
+        }
+    }
+}
+
+/// Longest wait between two attempts when the mempool is full
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Why a node bounced a submission, which decides how it is retried
+#[derive(Debug, PartialEq)]
+pub enum SubmitBounce {
+    /// the mempool is full, or has too many txs of the account, so wait longer each time
+    MempoolFull,
+    /// the sequence number was taken in the meantime, so sign again with the current one
+    SequenceNumber,
+    /// anything else, which a retry won't fix
+    Other,
+}
+
+/// Classifies a submission error, from the JSON-RPC mempool codes and VM statuses in it
+pub fn classify_submit_error(e: &Error) -> SubmitBounce {
+    let msg = format!("{:?}", e);
+    if ["MempoolIsFull", "TooManyTransactions", "-32008", "-32009"]
+        .iter()
+        .any(|m| msg.contains(m))
+    {
+        SubmitBounce::MempoolFull
+    } else if ["SEQUENCE_NUMBER_TOO_OLD", "SEQUENCE_NUMBER_TOO_NEW", "InvalidSeqNumber", "-32007"]
+        .iter()
+        .any(|m| msg.contains(m))
+    {
+        SubmitBounce::SequenceNumber
+    } else {
+        SubmitBounce::Other
+    }
+}
+
+/// Backoff before the next attempt, doubling with each attempt up to MAX_BACKOFF_SECS
+pub fn mempool_backoff(attempt: u32) -> time::Duration {
+    time::Duration::from_secs(2u64.saturating_pow(attempt).min(MAX_BACKOFF_SECS))
+}
+
+/// Like maybe_submit without saving, but retries with the policy, reporting each attempt.
+/// Each attempt signs again with the current sequence number of the signer, unless the
+/// tx which timed out has executed meanwhile.
+pub fn submit_with_policy(
+    script: TransactionPayload,
+    tx_params: &TxParams,
+    policy: &SubmitPolicy,
+) -> Result<SignedTransaction, Error> {
+    let attempts = policy.retries + 1;
+    let mut attempt = 1;
+    loop {
+        let mut client = DiemClient::new(tx_params.url.clone(), tx_params.waypoint)?;
+        let txn = match stage(script.clone(), tx_params, &mut client) {
+            Ok((_, txn)) => txn,
+            // the node may be briefly unreachable, so querying it is retried with the backoff
+            Err(e) if attempt < attempts => {
+                let wait = mempool_backoff(attempt);
+                println!("Attempt {} could not sign the tx, retrying in {:?}. Message: {:#}", attempt, wait, e);
+                thread::sleep(wait);
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(e.context(format!("tx not signed after {} attempts", attempts))),
+        };
+        println!("Submitting, attempt {} of {}", attempt, attempts);
+        let (bounce, timed_out) = match client.submit_transaction(&txn) {
+            Ok(_) => match wait_for_tx_timeout(
+                txn.sender(),
+                txn.sequence_number(),
+                &mut client,
+                policy.timeout_secs,
+            ) {
+                // a tx which executed and failed would fail again
+                Some(res) => return eval_tx_status(res).map(|_| txn),
+                // it may still have executed, and signing again would send it twice
+                None => match executed_after_timeout(&txn, &client) {
+                    Some(res) => return eval_tx_status(res).map(|_| txn),
+                    None => (
+                        Error::msg(format!("no tx status after {} seconds", policy.timeout_secs)),
+                        true,
+                    ),
+                },
+            },
+            Err(e) => (e, false),
+        };
+        if attempt == attempts {
+            return Err(bounce.context(format!("tx not executed after {} attempts", attempts)));
+        }
+        match (timed_out, classify_submit_error(&bounce)) {
+            (true, _) => println!("Attempt {} timed out, retrying", attempt),
+            (false, SubmitBounce::MempoolFull) => {
+                let wait = mempool_backoff(attempt);
+                println!("Attempt {} failed, mempool full, retrying in {:?}", attempt, wait);
+                thread::sleep(wait);
+            }
+            (false, SubmitBounce::SequenceNumber) => {
+                println!("Attempt {} failed, sequence number taken, signing again", attempt);
+                thread::sleep(time::Duration::from_secs(1));
+            }
+            (false, SubmitBounce::Other) => return Err(bounce),
+        }
+        attempt += 1;
+    }
+}
+
+/// The status of the tx, if the signer's account moved past its sequence number with this
+/// very tx. None if the tx is still pending, or another tx took the sequence number.
+pub fn executed_after_timeout(txn: &SignedTransaction, client: &DiemClient) -> Option<TransactionView> {
+    let on_chain = client.get_account(&txn.sender()).ok()??.sequence_number;
+    if on_chain <= txn.sequence_number() {
+        return None;
+    }
+    let hash = Transaction::UserTransaction(txn.clone()).hash();
+    client
+        .get_txn_by_acc_seq(&txn.sender(), txn.sequence_number(), false)
+        .ok()?
+        .filter(|view| view.hash == hash)
+}
+
 /// convenience for wrapping multiple transactions
 pub fn batch_wrapper(
     batch: Vec<TransactionPayload>,
@@ -135,20 +270,25 @@ fn stage(
     script: TransactionPayload,
     tx_params: &TxParams,
     client: &mut DiemClient,
-) -> (AccountData, SignedTransaction) {
+) -> Result<(AccountData, SignedTransaction), Error> {
     // let mut client = DiemClient::new(tx_params.url.clone(), tx_params.waypoint).unwrap();
 
-    let chain_id = ChainId::new(client.get_metadata().unwrap().chain_id);
+    let chain_id = ChainId::new(
+        client
+            .get_metadata()
+            .map_err(|e| e.context("could not get the chain id from the node"))?
+            .chain_id,
+    );
     let account_state = client
         .get_account(&tx_params.signer_address)
-        .unwrap();
+        .map_err(|e| e.context("could not get the sequence number of the signer from the node"))?;
 
     let sequence_number = match account_state {
         Some(av) => av.sequence_number,
         None => 0,
     };
     // Sign the transaction script
-    let txn = sign_tx(script, tx_params, sequence_number, chain_id).unwrap();
+    let txn = sign_tx(script, tx_params, sequence_number, chain_id)?;
 
     // Get account_data struct
     let signer_account_data = AccountData {
@@ -158,7 +298,7 @@ fn stage(
         sequence_number,
         status: AccountStatus::Persisted,
     };
-    (signer_account_data, txn)
+    Ok((signer_account_data, txn))
 }
 
 /// Submit a transaction to the network.
@@ -269,7 +409,7 @@ pub fn get_tx_params_from_swarm(
         auth_key,
         signer_address: address,
         owner_address: address,
-        url,
+        url: config.rpc_url(url),
         waypoint,
         keypair,
         tx_cost: TxCost {
@@ -341,17 +481,19 @@ pub fn get_tx_params_from_toml(
     is_swarm: bool,
 ) -> Result<TxParams, Error> {
     // let url = config.profile.default_node.clone().unwrap();
-    let (auth_key, address, wallet) = if let Some(wallet) = wallet_opt {
-        wallet::get_account_from_wallet(wallet)
+    // keys come from the wallet itself, since it may have been derived with a passphrase
+    let keys = if let Some(wallet) = wallet_opt {
+        KeyScheme::new(wallet)
     } else {
-        wallet::get_account_from_prompt()
+        KeyScheme::new(&wallet::get_account_from_prompt().2)
     };
+    let auth_key = keys.derived_auth_key();
+    let address = auth_key.derived_address();
 
     let waypoint = wp.unwrap_or_else(|| {
         config.get_waypoint(None).unwrap()
     });
 
-    let keys = KeyScheme::new_from_mnemonic(wallet.mnemonic());
     let keypair = KeyPair::from(keys.child_0_owner.get_private_key());
     let tx_cost = config.tx_configs.get_cost(tx_type);
 
@@ -384,13 +526,23 @@ pub fn wait_for_tx(
     signer_address: AccountAddress,
     sequence_number: u64,
     client: &mut DiemClient,
+) -> Option<TransactionView> {
+    wait_for_tx_timeout(signer_address, sequence_number, client, 60)
+}
+
+/// Wait for the response from the diem RPC, polling each second up to timeout_secs.
+pub fn wait_for_tx_timeout(
+    signer_address: AccountAddress,
+    sequence_number: u64,
+    client: &mut DiemClient,
+    timeout_secs: u64,
 ) -> Option<TransactionView> {
     println!(
         "\nAwaiting tx status \nSubmitted from account: {} with sequence number: {}",
         signer_address, sequence_number
     );
 
-    const MAX_ITERATIONS: u8 = 60;
+    let max_iterations = timeout_secs.max(1);
 
     let mut iter = 0;    
     loop {
@@ -412,7 +564,7 @@ pub fn wait_for_tx(
         }
         iter += 1;
 
-        if iter==MAX_ITERATIONS {
+        if iter==max_iterations {
             println!("Timeout waiting for response");
             return None;
         }        
@@ -467,3 +619,30 @@ impl TxParams {
         }
     }
 }
+
+#[test]
+fn test_classify_submit_error() {
+    let bounce = |m: &str| classify_submit_error(&Error::msg(m.to_owned()));
+
+    assert_eq!(bounce("mempool status: MempoolIsFull"), SubmitBounce::MempoolFull);
+    assert_eq!(bounce("mempool status: TooManyTransactions"), SubmitBounce::MempoolFull);
+    assert_eq!(bounce("JsonRpcError { code: -32008 }"), SubmitBounce::MempoolFull);
+    assert_eq!(bounce("JsonRpcError { code: -32009 }"), SubmitBounce::MempoolFull);
+
+    assert_eq!(bounce("vm status: SEQUENCE_NUMBER_TOO_OLD"), SubmitBounce::SequenceNumber);
+    assert_eq!(bounce("vm status: SEQUENCE_NUMBER_TOO_NEW"), SubmitBounce::SequenceNumber);
+    assert_eq!(bounce("mempool status: InvalidSeqNumber"), SubmitBounce::SequenceNumber);
+    assert_eq!(bounce("JsonRpcError { code: -32007 }"), SubmitBounce::SequenceNumber);
+
+    assert_eq!(bounce("vm status: INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE"), SubmitBounce::Other);
+}
+
+#[test]
+fn test_mempool_backoff() {
+    assert_eq!(mempool_backoff(1), time::Duration::from_secs(2));
+    assert_eq!(mempool_backoff(2), time::Duration::from_secs(4));
+    assert_eq!(mempool_backoff(5), time::Duration::from_secs(32));
+    assert_eq!(mempool_backoff(6), time::Duration::from_secs(MAX_BACKOFF_SECS));
+    // doesn't overflow after many attempts
+    assert_eq!(mempool_backoff(100), time::Duration::from_secs(MAX_BACKOFF_SECS));
+}
@@ -10,7 +10,7 @@ use diem_types::{
         },
         NetworkAddress,
     },
-    transaction::{SignedTransaction, TransactionPayload},
+    transaction::SignedTransaction,
 };
 
 use crate::pay_instruction::PayInstruction;
@@ -18,7 +18,7 @@ use anyhow;
 use hex::{decode, encode};
 use ol_keys::scheme::KeyScheme;
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use std::{fs::File, io::Write, path::PathBuf, process::exit};
+use std::{fs::File, io::Write, path::PathBuf};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// Configuration data necessary to initialize a validator.
@@ -49,6 +49,106 @@ pub struct ValConfigs {
     pub autopay_instructions: Option<Vec<PayInstruction>>,
     /// autopay configs
     pub autopay_signed: Option<Vec<SignedTransaction>>,
+    /// block zero has not been mined yet, the manifest cannot be used for onboarding until it is imported
+    #[serde(default)]
+    pub proof_pending: bool,
+    /// how the network can reach the operator, e.g. an email, matrix handle or URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    /// schema version of the manifest. Older binaries didn't write it, a manifest without it
+    /// which deserializes has the fields of the current version.
+    #[serde(default = "current_manifest_schema_version")]
+    pub schema_version: u64,
+}
+
+fn current_manifest_schema_version() -> u64 {
+    MANIFEST_SCHEMA_VERSION
+}
+
+/// Schema version of the account.json this binary writes. Version 1 manifests, of older
+/// binaries, have no op_fullnode_network_addresses_string.
+pub const MANIFEST_SCHEMA_VERSION: u64 = 2;
+
+/// File name of the operator fields shared with the owner
+pub const OPERATOR_FILE: &str = "operator.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The operator fields of ValConfigs, which an operator shares with the owner when they
+/// are different people, so the owner can write a manifest delegating to the operator.
+pub struct OperatorConfigs {
+    /// Owner account the operator runs the node for, which the network addresses are encrypted for
+    pub owner_address: String,
+    /// Operator account
+    pub op_address: String,
+    /// Auth key prefix of Operator
+    #[serde(serialize_with = "as_hex", deserialize_with = "from_hex")]
+    pub op_auth_key_prefix: Vec<u8>,
+    /// Key validator will use in consensus
+    #[serde(serialize_with = "as_hex", deserialize_with = "from_hex")]
+    pub op_consensus_pubkey: Vec<u8>,
+    /// Key validator will use for network connections
+    #[serde(serialize_with = "as_hex", deserialize_with = "from_hex")]
+    pub op_validator_network_addresses: Vec<u8>,
+    /// FullNode will use for network connections
+    #[serde(serialize_with = "as_hex", deserialize_with = "from_hex")]
+    pub op_fullnode_network_addresses: Vec<u8>,
+    /// FullNode will use for network connections
+    pub op_fullnode_network_addresses_string: NetworkAddress,
+    /// Human readable name of account
+    pub op_human_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// BCS representation of ValConfigs, for tools submitting the manifest directly.
+/// Binary fields are raw bytes instead of hex, and autopay is only included as the
+/// signed transactions, since BCS has no floats for the instruction values.
+pub struct ValConfigsBcs {
+    /// Block zero height
+    pub block_zero_height: u64,
+    /// Block zero elapsed time in seconds
+    pub block_zero_elapsed_secs: u64,
+    /// Block zero VDF preimage
+    pub block_zero_preimage: Vec<u8>,
+    /// Block zero VDF proof
+    pub block_zero_proof: Vec<u8>,
+    /// Owner account
+    pub ow_human_name: String,
+    /// Operator account
+    pub op_address: String,
+    /// Auth key prefix of Operator
+    pub op_auth_key_prefix: Vec<u8>,
+    /// Key validator will use in consensus
+    pub op_consensus_pubkey: Vec<u8>,
+    /// Key validator will use for network connections
+    pub op_validator_network_addresses: Vec<u8>,
+    /// FullNode will use for network connections
+    pub op_fullnode_network_addresses: Vec<u8>,
+    /// Human readable name of account
+    pub op_human_name: String,
+    /// autopay signed transactions
+    pub autopay_signed: Option<Vec<SignedTransaction>>,
+    /// block zero has not been mined yet
+    pub proof_pending: bool,
+}
+
+impl From<&ValConfigs> for ValConfigsBcs {
+    fn from(val: &ValConfigs) -> Self {
+        ValConfigsBcs {
+            block_zero_height: val.block_zero.height,
+            block_zero_elapsed_secs: val.block_zero.elapsed_secs,
+            block_zero_preimage: val.block_zero.preimage.clone(),
+            block_zero_proof: val.block_zero.proof.clone(),
+            ow_human_name: val.ow_human_name.clone(),
+            op_address: val.op_address.clone(),
+            op_auth_key_prefix: val.op_auth_key_prefix.clone(),
+            op_consensus_pubkey: val.op_consensus_pubkey.clone(),
+            op_validator_network_addresses: val.op_validator_network_addresses.clone(),
+            op_fullnode_network_addresses: val.op_fullnode_network_addresses.clone(),
+            op_human_name: val.op_human_name.clone(),
+            autopay_signed: val.autopay_signed.clone(),
+            proof_pending: val.proof_pending,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -74,17 +174,10 @@ where
     decode(s).map_err(D::Error::custom)
 }
 
-impl ValConfigs {
-    /// New val config.
-    pub fn new(
-        block: Block,
-        keys: KeyScheme,
-        ip_address: String,
-        autopay_instructions: Option<Vec<PayInstruction>>,
-        autopay_signed: Option<Vec<SignedTransaction>>,
-    ) -> Self {
-
-      let owner_address = keys.child_0_owner.get_address().to_string();
+impl OperatorConfigs {
+    /// Operator fields of the keys, for the node at the ip address run for the owner account.
+    pub fn new(keys: &KeyScheme, ip_address: String, owner: AccountAddress) -> Self {
+        let owner_address = owner.to_string();
         
         // Create the list of validator addresses
         let val_network_string = format!("/ip4/{}/tcp/6180", ip_address);
@@ -99,9 +192,7 @@ impl ValConfigs {
             .encrypt(
                 &TEST_SHARED_VAL_NETADDR_KEY,        //shared_val_netaddr_key: &Key,
                 TEST_SHARED_VAL_NETADDR_KEY_VERSION, //key_version: KeyVersion,
-                &owner_address
-                    .parse::<AccountAddress>()
-                    .expect("unable to parse account address"), // account: &AccountAddress,
+                &owner,                              // account: &AccountAddress,
                 0,
                 0,
             )
@@ -119,9 +210,7 @@ impl ValConfigs {
         let fn_addr_obj = fn_addr_obj.append_prod_protos(fn_pubkey, 0);
         
         Self {
-            /// Block zero of the onboarded miner
-            block_zero: block,
-            ow_human_name: owner_address.clone(),
+            owner_address: owner_address.clone(),
             op_address: keys.child_1_operator.get_address().to_string(),
             op_auth_key_prefix: keys
                 .child_1_operator
@@ -133,10 +222,100 @@ impl ValConfigs {
             op_fullnode_network_addresses: bcs::to_bytes(&vec![&fn_addr_obj]).unwrap(),
             op_fullnode_network_addresses_string: fn_addr_obj.to_owned(),
             op_human_name: format!("{}-oper", owner_address),
+        }
+    }
+
+    /// Writes operator.json, to send to the owner
+    pub fn create_file(&self, mut json_path: PathBuf) -> Result<PathBuf, anyhow::Error> {
+        json_path.push(OPERATOR_FILE);
+        File::create(json_path.as_path())?.write_all(serde_json::to_string_pretty(&self)?.as_bytes())?;
+        Ok(json_path)
+    }
+
+    /// Reads an operator.json
+    pub fn read_file(path: &PathBuf) -> Result<OperatorConfigs, anyhow::Error> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+}
+
+impl ValConfigs {
+    /// New val config.
+    pub fn new(
+        block: Block,
+        keys: KeyScheme,
+        ip_address: String,
+        autopay_instructions: Option<Vec<PayInstruction>>,
+        autopay_signed: Option<Vec<SignedTransaction>>,
+    ) -> Self {
+        let operator = OperatorConfigs::new(&keys, ip_address, keys.derived_address());
+        ValConfigs::from_operator(block, operator, autopay_instructions, autopay_signed)
+    }
+
+    /// Val config of an owner delegating to the operator, whose keys the owner doesn't have.
+    pub fn from_operator(
+        block: Block,
+        operator: OperatorConfigs,
+        autopay_instructions: Option<Vec<PayInstruction>>,
+        autopay_signed: Option<Vec<SignedTransaction>>,
+    ) -> Self {
+        Self {
+            /// Block zero of the onboarded miner
+            block_zero: block,
+            ow_human_name: operator.owner_address,
+            op_address: operator.op_address,
+            op_auth_key_prefix: operator.op_auth_key_prefix,
+            op_consensus_pubkey: operator.op_consensus_pubkey,
+            op_validator_network_addresses: operator.op_validator_network_addresses,
+            op_fullnode_network_addresses: operator.op_fullnode_network_addresses,
+            op_fullnode_network_addresses_string: operator.op_fullnode_network_addresses_string,
+            op_human_name: operator.op_human_name,
             autopay_instructions,
             autopay_signed,
+            proof_pending: false,
+            contact: None,
+            schema_version: MANIFEST_SCHEMA_VERSION,
         }
     }
+
+    /// Roles of the private keys whose bytes are in the json of the manifest, as hex or as a byte array
+    pub fn private_material_leaks(&self, private_keys: &[(&'static str, Vec<u8>)]) -> Vec<&'static str> {
+        let json = serde_json::to_string(&self).expect("Config should be export to json");
+        private_keys
+            .iter()
+            .filter(|(_, bytes)| {
+                let as_array: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+                json.contains(&encode(bytes)) || json.contains(&as_array.join(","))
+            })
+            .map(|(role, _)| *role)
+            .collect()
+    }
+
+    /// Creates account.json like create_manifest, after checking none of the private keys are in it.
+    /// With include_private the check is skipped, e.g. for a manifest kept with the key store.
+    pub fn create_manifest_checked(
+        &self,
+        json_path: PathBuf,
+        private_keys: &[(&'static str, Vec<u8>)],
+        include_private: bool,
+    ) -> Result<(), anyhow::Error> {
+        if include_private {
+            println!("\n{}", "!".repeat(72));
+            println!("WARN: --manifest-include-private is set, account.json is not checked for private keys.");
+            println!("WARN: do not share this account.json, anyone with it may control the account.");
+            println!("{}\n", "!".repeat(72));
+        } else {
+            let leaks = self.private_material_leaks(private_keys);
+            if !leaks.is_empty() {
+                anyhow::bail!(
+                    "account.json would contain the private keys of: {}, not writing it",
+                    leaks.join(", ")
+                );
+            }
+        }
+        self.create_manifest(json_path);
+        Ok(())
+    }
+
     /// Creates the json file needed for onchain account creation - validator
     pub fn create_manifest(&self, mut json_path: PathBuf) {
         //where file will be saved
@@ -148,6 +327,15 @@ impl ValConfigs {
         println!("account manifest created, file saved to: {:?}", json_path);
     }
 
+    /// Creates account.bcs next to account.json, for tools which consume BCS directly
+    pub fn create_manifest_bcs(&self, mut bcs_path: PathBuf) -> Result<(), anyhow::Error> {
+        bcs_path.push("account.bcs");
+        let buf = bcs::to_bytes(&ValConfigsBcs::from(self))?;
+        File::create(bcs_path.as_path())?.write_all(&buf)?;
+        println!("account manifest created, file saved to: {:?}", bcs_path);
+        Ok(())
+    }
+
     /// Extract the preimage and proof from a genesis proof block_0.json
     pub fn get_init_data(path: &PathBuf) -> Result<ValConfigs, std::io::Error> {
         let file = std::fs::File::open(path)?;
@@ -157,39 +345,70 @@ impl ValConfigs {
         return Ok(configs);
     }
 
-    /// check correctness of autopay
-    pub fn check_autopay(&self) -> Result<(), anyhow::Error> {
-        self.autopay_instructions
-            .clone()
-            .expect("could not find autopay instructions")
-            .into_iter()
-            .enumerate()
-            .for_each(|(i, instr)| {
+    /// Schema version of the json of an account.json, from its schema_version, or by the fields it has
+    pub fn manifest_version(json: &serde_json::Value) -> u64 {
+        if let Some(v) = json.get("schema_version").and_then(|v| v.as_u64()) {
+            return v;
+        }
+        if json.get("op_fullnode_network_addresses_string").is_some() {
+            MANIFEST_SCHEMA_VERSION
+        } else {
+            1
+        }
+    }
+
+    /// Reads the json of an account.json of an older schema version as the current ValConfigs.
+    /// The fullnode address string of version 1 is decoded from the BCS fullnode addresses.
+    pub fn migrate(mut json: serde_json::Value) -> Result<ValConfigs, anyhow::Error> {
+        match ValConfigs::manifest_version(&json) {
+            1 => {
+                let addresses = json
+                    .get("op_fullnode_network_addresses")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("the manifest has no op_fullnode_network_addresses"))?;
+                let bytes = decode(addresses)?;
+                // older binaries wrote one address, newer ones a list of them
+                let address = bcs::from_bytes::<Vec<NetworkAddress>>(&bytes)
+                    .ok()
+                    .and_then(|a| a.into_iter().next())
+                    .or_else(|| bcs::from_bytes::<NetworkAddress>(&bytes).ok())
+                    .ok_or_else(|| anyhow::anyhow!("op_fullnode_network_addresses is not a BCS network address"))?;
+                json["op_fullnode_network_addresses_string"] = serde_json::to_value(&address)?;
+            }
+            v if v == MANIFEST_SCHEMA_VERSION => {}
+            v => anyhow::bail!(
+                "the manifest has schema version {}, this binary reads up to version {}",
+                v,
+                MANIFEST_SCHEMA_VERSION
+            ),
+        }
+        json["schema_version"] = serde_json::to_value(MANIFEST_SCHEMA_VERSION)?;
+        Ok(serde_json::from_value(json)?)
+    }
+
+    /// Check there is one signed autopay tx per autopay instruction, and that each matches its
+    /// instruction. With confirm, the operator also accepts each instruction on the terminal.
+    pub fn check_autopay(&self, confirm: bool) -> Result<(), anyhow::Error> {
+        let instructions = self.autopay_instructions.clone().unwrap_or_default();
+        let signed = self.autopay_signed.clone().unwrap_or_default();
+        if instructions.len() != signed.len() {
+            anyhow::bail!(
+                "{} autopay instructions but {} signed transactions",
+                instructions.len(),
+                signed.len()
+            );
+        }
+        for (instr, tx) in instructions.iter().zip(signed.iter()) {
+            instr.check_instruction_match_payload(tx.payload()).map_err(|e| {
+                anyhow::anyhow!("autopay instruction does not match signed tx args, {:?}, error: {}", instr, e)
+            })?;
+            if confirm && !*IS_TEST {
                 println!("{}", instr.text_instruction());
-                if !*IS_TEST {  
-                  match Confirm::new().with_prompt("").interact().unwrap() {
-                    true => {},
-                    _ =>  {
-                      print!("Autopay configuration aborted. Check batch configuration file or template");
-                      exit(1);
-                    }
-                  } 
+                if !Confirm::new().with_prompt("").interact()? {
+                    anyhow::bail!("Autopay configuration aborted. Check batch configuration file or template");
                 }
-                let signed = self.autopay_signed.clone().unwrap();
-                let tx = signed.iter().nth(i).unwrap();
-                let payload = tx.clone().into_raw_transaction().into_payload();
-                if let TransactionPayload::Script(s) = payload {
-                    match instr.check_instruction_match_tx(s.clone()) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            panic!(
-                            "autopay instruction does not match signed tx args, {:?}, error: {}",
-                            instr, e
-                        );
-                        }
-                    }
-                };
-            });
+            }
+        }
         Ok(())
     }
 }
@@ -278,3 +497,52 @@ fn val_config_ip_address() {
         dec_addrs.to_string(),
         "/ip4/161.35.13.169/tcp/6180/ln-noise-ik/151bcbc2adf48aefee3492a3c802ce35e347860f28dbcffe74068419f3b11812/ln-handshake/0".to_string());
 }
+
+#[test]
+fn val_config_bcs_roundtrip() {
+    let block = Block {
+        height: 0u64,
+        elapsed_secs: 10u64,
+        preimage: vec![1, 2, 3],
+        proof: vec![4, 5, 6],
+    };
+    let eve_keys = KeyScheme::new_from_mnemonic("recall october regret kite undo choice outside season business wall quit arrest vacant arrow giggle vote ghost winter hawk soft cheap decide exhaust spare".to_string());
+    let val = ValConfigs::new(block, eve_keys, "161.35.13.169".to_string(), None, None);
+
+    let bcs_val = ValConfigsBcs::from(&val);
+    let bytes = bcs::to_bytes(&bcs_val).unwrap();
+    let decoded: ValConfigsBcs = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, bcs_val);
+    assert_eq!(decoded.block_zero_proof, vec![4, 5, 6]);
+    assert_eq!(decoded.op_consensus_pubkey, val.op_consensus_pubkey);
+}
+
+#[test]
+fn private_material_leaks() {
+    let keys = KeyScheme::new_from_mnemonic("recall october regret kite undo choice outside season business wall quit arrest vacant arrow giggle vote ghost winter hawk soft cheap decide exhaust spare".to_string());
+    let private_keys = keys.private_key_bytes();
+    let mut val = ValConfigs::new(Block::default(), keys, "161.35.13.169".to_string(), None, None);
+    assert!(val.private_material_leaks(&private_keys).is_empty());
+
+    val.op_human_name = encode(&private_keys[4].1);
+    assert_eq!(val.private_material_leaks(&private_keys), vec!["consensus"]);
+}
+
+#[test]
+fn migrate_version_1_manifest() {
+    let path = ol_fixtures::get_persona_account_json("eve").1;
+    let mut json: serde_json::Value = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+    let current = ValConfigs::get_init_data(&path).unwrap();
+    assert_eq!(ValConfigs::manifest_version(&json), MANIFEST_SCHEMA_VERSION);
+
+    json.as_object_mut().unwrap().remove("op_fullnode_network_addresses_string");
+    assert_eq!(ValConfigs::manifest_version(&json), 1);
+    let migrated = ValConfigs::migrate(json).unwrap();
+    assert_eq!(
+        migrated.op_fullnode_network_addresses_string,
+        current.op_fullnode_network_addresses_string
+    );
+    assert_eq!(migrated.schema_version, MANIFEST_SCHEMA_VERSION);
+    let written = serde_json::to_value(&migrated).unwrap();
+    assert_eq!(written["schema_version"].as_u64(), Some(MANIFEST_SCHEMA_VERSION));
+}
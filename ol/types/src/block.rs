@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use std::{fs, io::BufReader, path::PathBuf};
 /// Data structure and serialization of 0L delay proof.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Block {
     /// Block Height
     pub height: u64,
@@ -1,6 +1,6 @@
 //! Configs for all 0L apps.
 
-use anyhow::Error;
+use anyhow::{bail, Context, Error};
 use dirs;
 use diem_config::config::NodeConfig;
 use diem_global_constants::{CONFIG_FILE, NODE_HOME};
@@ -13,15 +13,45 @@ use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::{
+    collections::BTreeMap,
     fs::{self, File}, 
     io::{Read, Write}, 
     net::Ipv4Addr, 
-    path::PathBuf, 
+    path::{Path, PathBuf},
     str::FromStr
 };
 
 use crate::dialogue::{what_home, what_ip, what_statement};
 
+/// Key in the metadata of 0L.toml of how the network can reach the operator
+pub const CONTACT_METADATA_KEY: &str = "contact";
+
+/// Stdlib binary of a source checkout, relative to its root, as written by `make stdlib`
+pub const STDLIB_BIN_PATH: &str = "language/diem-framework/staged/stdlib.mv";
+
+/// Checks the source path is the root of a built checkout of the repo: it has the
+/// workspace Cargo.toml, the ol crates, and the stdlib binary.
+pub fn check_source_path(source_path: &Path) -> Result<(), Error> {
+    if !source_path.is_dir() {
+        bail!("source path {:?} is not a directory, pass the root of the libra repo", source_path);
+    }
+    if !source_path.join("Cargo.toml").exists() || !source_path.join("ol").is_dir() {
+        bail!(
+            "source path {:?} is not the root of the libra repo, it has no Cargo.toml and ol directory",
+            source_path
+        );
+    }
+    let stdlib = source_path.join(STDLIB_BIN_PATH);
+    if !stdlib.exists() {
+        bail!(
+            "no stdlib built in {:?}, run `make stdlib` in {:?} first",
+            stdlib,
+            source_path
+        );
+    }
+    Ok(())
+}
+
 const BASE_WAYPOINT: &str = "0:683185844ef67e5c8eeaa158e635de2a4c574ce7bbb7f41f787d38db2d623ae2";
 
 /// Check if we are in prod mode
@@ -63,6 +93,9 @@ pub struct AppCfg {
     pub chain_info: ChainInfo,
     /// Transaction configurations
     pub tx_configs: TxConfigs,
+    /// Operator notes about this node, e.g. operator name or datacenter. Not read by the node.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// Get a AppCfg object from toml file
@@ -139,7 +172,7 @@ impl AppCfg {
         source_path: &Option<PathBuf>,
         statement: Option<String>,
         ip: Option<Ipv4Addr>,        
-    ) -> AppCfg {
+    ) -> Result<AppCfg, Error> {
         // TODO: Check if configs exist and warn on overwrite.
         let mut default_config = AppCfg::default();
         default_config.profile.auth_key = authkey.to_string();
@@ -153,18 +186,16 @@ impl AppCfg {
 
         default_config.profile.ip = match ip {
             Some(i) => i,
-            None => what_ip().unwrap(),
+            None => what_ip()?,
         };
         default_config.workspace.node_home = config_path.clone().unwrap_or_else(||{
             what_home(None, None)
         });
 
-        if source_path.is_some() {
-            // let source_path = what_source();
-            default_config.workspace.source_path = source_path.clone();
-            default_config.workspace.stdlib_bin_path = Some(
-                source_path.as_ref().unwrap().join("language/stdlib/staged/stdlib.mv")
-            );
+        if let Some(path) = source_path {
+            check_source_path(path)?;
+            default_config.workspace.source_path = Some(path.clone());
+            default_config.workspace.stdlib_bin_path = Some(path.join(STDLIB_BIN_PATH));
         }
 
         // override from args
@@ -175,9 +206,14 @@ impl AppCfg {
           if let Some(url) = upstream_peer {
               default_config.profile.upstream_nodes = Some(vec![url.to_owned()]);
               let mut web_monitor_url = url.clone();
-              web_monitor_url.set_port(Some(3030)).unwrap();
-              let epoch_url = &web_monitor_url.join("epoch.json").unwrap();
-              let (e, w) = bootstrap_waypoint_from_upstream(epoch_url).unwrap();
+              web_monitor_url.set_port(Some(3030)).map_err(|_| {
+                  Error::msg(format!(
+                      "upstream peer URL {} is malformed, expected the scheme://host form, e.g. http://1.2.3.4",
+                      url
+                  ))
+              })?;
+              let epoch_url = &web_monitor_url.join("epoch.json")?;
+              let (e, w) = bootstrap_waypoint_from_upstream(epoch_url)?;
               default_config.chain_info.base_epoch = Some(e);
               default_config.chain_info.base_waypoint = Some(w);
           } else {
@@ -190,14 +226,16 @@ impl AppCfg {
 
         // skip questionnaire if CI
         if *IS_TEST {
-            default_config.save_file();
+            default_config.save_file()?;
 
-            return default_config;
+            return Ok(default_config);
         }
-        fs::create_dir_all(&default_config.workspace.node_home).unwrap();
-        default_config.save_file();
+        let home_path = &default_config.workspace.node_home;
+        fs::create_dir_all(home_path)
+            .with_context(|| format!("could not create node home {:?}", home_path))?;
+        default_config.save_file()?;
 
-        default_config
+        Ok(default_config)
     }
 
   /// Save swarm default configs to swarm path
@@ -208,7 +246,7 @@ impl AppCfg {
     ) -> AppCfg{
         // println!("init_swarm_config: {:?}", swarm_path); already logged in commands.rs
         let host_config = AppCfg::make_swarm_configs(swarm_path, node_home, source_path);
-        host_config.save_file();
+        host_config.save_file().expect("could not save swarm app configs");
         host_config
   }
 
@@ -253,6 +291,7 @@ impl AppCfg {
             profile: Profile::default(),
             chain_info: ChainInfo::default(),
             tx_configs: TxConfigs::default(),
+            metadata: BTreeMap::new(),
         };
 
         let db_path = node_home.join("db");
@@ -267,9 +306,10 @@ impl AppCfg {
 
         cfg
     }
-    /// choose a node to connect to, either localhost or upstream
+    /// choose a node to connect to, either localhost or upstream.
+    /// The URL is https if the profile uses TLS for JSON-RPC.
     pub fn what_url(&self, use_upstream_url: bool) -> Url {
-        if use_upstream_url {
+        let url = if use_upstream_url {
             self.profile
                 .upstream_nodes
                 .clone()
@@ -282,24 +322,35 @@ impl AppCfg {
                 .default_node
                 .clone()
                 .expect("no url provided in config toml")
+        };
+        self.rpc_url(url)
+    }
+
+    /// the url to use for JSON-RPC, switched to https if the profile uses TLS
+    pub fn rpc_url(&self, mut url: Url) -> Url {
+        if self.profile.rpc_tls && url.scheme() == "http" {
+            url.set_scheme("https").expect("could not set https scheme");
         }
+        url
     }
 
     /// save the config file to 0L.toml to the workspace home path
-    pub fn save_file(&self) {
-        let toml = toml::to_string(&self).unwrap();
+    pub fn save_file(&self) -> Result<(), Error> {
+        let toml = toml::to_string(&self)?;
         let home_path = &self.workspace.node_home.clone();
         // create home path if doesn't exist, usually only in dev/ci environments.
-        fs::create_dir_all(&home_path).expect("could not create 0L home directory");
+        fs::create_dir_all(&home_path)
+            .with_context(|| format!("could not create 0L home directory {:?}", home_path))?;
         let toml_path = home_path.join(CONFIG_FILE);
-        let file = fs::File::create(&toml_path);
-        file.unwrap()
-            .write(&toml.as_bytes())
-            .expect("Could not write toml file");
+        let mut file = fs::File::create(&toml_path)
+            .with_context(|| format!("could not create {:?}", &toml_path))?;
+        file.write(&toml.as_bytes())
+            .with_context(|| format!("could not write {:?}", &toml_path))?;
         println!(
             "\nhost configs initialized, file saved to: {:?}",
             &toml_path
         );
+        Ok(())
     }
 }
 
@@ -314,6 +365,7 @@ impl Default for AppCfg {
             profile: Profile::default(),
             chain_info: ChainInfo::default(),
             tx_configs: TxConfigs::default(),
+            metadata: BTreeMap::new(),
         }
     }
 }
@@ -332,7 +384,7 @@ pub struct Workspace {
     #[serde(default = "default_db_path")]
     pub db_path: PathBuf,
     /// Path to which stdlib binaries for upgrades get built typically 
-    /// /language/stdlib/staged/stdlib.mv
+    /// /language/diem-framework/staged/stdlib.mv
     pub stdlib_bin_path: Option<PathBuf>,
 }
 
@@ -398,6 +450,10 @@ pub struct Profile {
 
     /// Other nodes to connect for fallback connections
     pub upstream_nodes: Option<Vec<Url>>,
+
+    /// Connect to the node URLs over https, for JSON-RPC fronted with TLS
+    #[serde(default)]
+    pub rpc_tls: bool,
 }
 
 impl Default for Profile {
@@ -409,6 +465,7 @@ impl Default for Profile {
             ip: "0.0.0.0".parse().unwrap(),
             default_node: Some("http://localhost:8080".parse().expect("parse url")),
             upstream_nodes: Some(vec!["http://localhost:8080".parse().expect("parse url")]),
+            rpc_tls: false,
         }
     }
 }
@@ -546,17 +603,19 @@ pub fn get_swarm_backup_service_url(
 
 /// fetch initial waypoint information from a clean state.
 pub fn bootstrap_waypoint_from_upstream(url: &Url) -> Result<(u64, Waypoint), Error> {
-    let g_res = reqwest::blocking::get(&url.to_string());
-    let string = g_res.unwrap().text().unwrap();
-    let json: serde_json::Value = string.parse().unwrap();
-    let epoch = json.get("epoch").unwrap().as_u64().unwrap();
+    let string = reqwest::blocking::get(&url.to_string())
+        .and_then(|r| r.text())
+        .with_context(|| format!("could not fetch epoch info from {}", url))?;
+    let json: serde_json::Value = string.parse()?;
+    let epoch = json
+        .get("epoch")
+        .and_then(|e| e.as_u64())
+        .ok_or_else(|| Error::msg(format!("no epoch found at {}", url)))?;
     let waypoint = json
         .get("waypoint")
-        .unwrap()
-        .as_str()
-        .unwrap()
-        .parse()
-        .unwrap();
+        .and_then(|w| w.as_str())
+        .ok_or_else(|| Error::msg(format!("no waypoint found at {}", url)))?
+        .parse()?;
 
     Ok((epoch, waypoint))
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+//! Checksum of the keys of a key_store.json, to find a key store changed since it was written
+
+use anyhow::{bail, Context, Error};
+use diem_crypto::HashValue;
+use diem_global_constants::{GENESIS_WAYPOINT, SAFETY_DATA, WAYPOINT};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// File name of the key store in the node home
+pub const KEY_STORE_FILE: &str = "key_store.json";
+
+/// Sidecar of the key store with the checksum of its keys
+pub const CHECKSUM_FILE: &str = "key_store.json.sha3";
+
+/// Entries the node and wizard update after the keys are written, left out of the checksum
+const MUTABLE_ENTRIES: [&str; 3] = [SAFETY_DATA, WAYPOINT, GENESIS_WAYPOINT];
+
+/// Sha3 of the key store entries, except the ones updated while the node runs
+fn checksum(home_path: &Path) -> Result<HashValue, Error> {
+    let path = home_path.join(KEY_STORE_FILE);
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("could not read key store {:?}", &path))?;
+    let entries: Map<String, Value> = serde_json::from_str(&text)
+        .with_context(|| format!("key store {:?} is not valid json", &path))?;
+    let keys: Map<String, Value> = entries
+        .into_iter()
+        .filter(|(k, _)| !MUTABLE_ENTRIES.contains(&k.rsplit('/').next().unwrap_or("")))
+        .collect();
+    Ok(HashValue::sha3_256_of(serde_json::to_string(&keys)?.as_bytes()))
+}
+
+/// Writes the checksum sidecar of the key store in the node home
+pub fn write_checksum(home_path: &Path) -> Result<PathBuf, Error> {
+    let path = home_path.join(CHECKSUM_FILE);
+    fs::write(&path, checksum(home_path)?.to_hex())
+        .with_context(|| format!("could not write checksum {:?}", &path))?;
+    Ok(path)
+}
+
+/// Outcome of a key store checksum verification which didn't find a mismatch
+#[derive(Debug, PartialEq)]
+pub enum ChecksumStatus {
+    /// the keys match the checksum
+    Verified,
+    /// no checksum, the key store was written by an older version
+    Missing,
+}
+
+/// Recomputes the checksum of the key store and compares it to the sidecar.
+/// Errors if they differ.
+pub fn verify_checksum(home_path: &Path) -> Result<ChecksumStatus, Error> {
+    let path = home_path.join(CHECKSUM_FILE);
+    if !path.exists() {
+        return Ok(ChecksumStatus::Missing);
+    }
+    let expected = fs::read_to_string(&path)
+        .with_context(|| format!("could not read checksum {:?}", &path))?;
+    let computed = checksum(home_path)?.to_hex();
+    if expected.trim() != computed {
+        bail!(
+            "the keys in {:?} don't match the checksum {:?}, it was changed since it was written",
+            home_path.join(KEY_STORE_FILE),
+            &path
+        );
+    }
+    Ok(ChecksumStatus::Verified)
+}
+
+#[test]
+fn test_verify_checksum() {
+    use diem_global_constants::OWNER_ACCOUNT;
+
+    let home = std::env::temp_dir().join("key_store_checksum_test");
+    let _ = fs::remove_dir_all(&home);
+    fs::create_dir_all(&home).unwrap();
+    let write_store = |owner: &str, waypoint: &str| {
+        let store = serde_json::json!({
+            format!("0a-oper/{}", OWNER_ACCOUNT): owner,
+            format!("0a-oper/{}", SAFETY_DATA): waypoint,
+            WAYPOINT: waypoint,
+        });
+        fs::write(home.join(KEY_STORE_FILE), store.to_string()).unwrap();
+    };
+
+    write_store("0a", "0:aa");
+    assert_eq!(verify_checksum(&home).unwrap(), ChecksumStatus::Missing);
+    write_checksum(&home).unwrap();
+    assert_eq!(verify_checksum(&home).unwrap(), ChecksumStatus::Verified);
+
+    // the node updates the mutable entries, which the checksum leaves out
+    write_store("0a", "1:bb");
+    assert_eq!(verify_checksum(&home).unwrap(), ChecksumStatus::Verified);
+
+    write_store("0b", "1:bb");
+    assert!(verify_checksum(&home).is_err());
+
+    fs::remove_dir_all(&home).unwrap();
+}
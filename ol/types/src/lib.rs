@@ -17,4 +17,5 @@ pub mod dialogue;
 pub mod autopay;
 pub mod validator_config;
 pub mod fullnode_counter;
+pub mod key_store_checksum;
 pub mod wallet;
@@ -1,13 +1,13 @@
 //! `autopay`
 
-use anyhow::Error;
+use anyhow::{bail, Context, Error};
 use diem_types::{
     account_address::AccountAddress,
-    transaction::{Script, TransactionArgument},
+    transaction::{Script, ScriptFunction, TransactionArgument, TransactionPayload},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{fs::{self, File}, io::Write, path::PathBuf, process::exit, u64};
+use std::{fmt, fs::{self, File}, io::Write, path::PathBuf, u64};
 
 // These match Autpay2.move
 /// send percent of balance at end of epoch payment type
@@ -19,6 +19,11 @@ const FIXED_RECURRING: u8 = 2;
 /// send a certain amount once at the next tick payment type
 const FIXED_ONCE: u8 = 3;
 
+/// Coin amounts on chain are in micro units, with this many decimals
+pub const COIN_DECIMALS: u32 = 6;
+/// Name of the coin in summaries
+pub const COIN_NAME: &str = "GAS";
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 /// Types of instructions for autopay
@@ -57,20 +62,138 @@ pub struct PayInstruction {
     pub duration_epochs: Option<u64>,
 }
 
+/// An autopay instruction which can't be turned into a transaction
+#[derive(Debug)]
+pub struct InstructionError {
+    /// position of the instruction in the batch
+    pub index: usize,
+    /// the instruction
+    pub instruction: PayInstruction,
+    /// what is wrong with the instruction
+    pub reason: String,
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {} (uid {:?}): {}", self.index, self.instruction.uid, self.reason)
+    }
+}
+
+/// All the instructions of a batch which can't be turned into transactions, as one error
+#[derive(Debug)]
+pub struct InstructionErrors(pub Vec<InstructionError>);
+
+impl fmt::Display for InstructionErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "found {} problems in the autopay instructions", self.0.len())?;
+        self.0.iter().try_for_each(|e| write!(f, "\n{}", e))
+    }
+}
+
+impl std::error::Error for InstructionErrors {}
+
 impl PayInstruction {
-    /// extract autopay instructions from json file
+    /// extract autopay instructions from json file.
+    /// A start offset shifts the epochs of all instructions, e.g. for a template reused on another chain.
     pub fn parse_autopay_instructions(
         autopay_batch_file: &PathBuf,
         current_epoch: Option<u64>,
         start_uid: Option<u64>,
+        start_offset: Option<i64>,
+    ) -> Result<Vec<PayInstruction>, Error> {
+        let inst_vec = PayInstruction::read_instructions(autopay_batch_file)?;
+        PayInstruction::transform_instructions(inst_vec, current_epoch, start_uid, start_offset)
+    }
+
+    /// Merge the instructions of all the *.json and *.csv files in a directory, sorted by file name,
+    /// and parse them as one set. Also returns how many instructions came from each file.
+    pub fn parse_autopay_dir(
+        autopay_dir: &PathBuf,
+        current_epoch: Option<u64>,
+        start_uid: Option<u64>,
+        start_offset: Option<i64>,
+    ) -> Result<(Vec<PayInstruction>, Vec<(PathBuf, usize)>), Error> {
+        let mut files: Vec<PathBuf> = fs::read_dir(autopay_dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().map_or(false, |e| e == "json" || e == "csv"))
+            .collect();
+        files.sort();
+        if files.is_empty() {
+            return Err(Error::msg(format!("no json or csv files in autopay directory {:?}", autopay_dir)));
+        }
+
+        let mut inst_vec = vec![];
+        let mut counts = vec![];
+        for f in files {
+            let mut file_inst = PayInstruction::read_instructions(&f)?;
+            counts.push((f, file_inst.len()));
+            inst_vec.append(&mut file_inst);
+        }
+        let transformed =
+            PayInstruction::transform_instructions(inst_vec, current_epoch, start_uid, start_offset)?;
+        Ok((transformed, counts))
+    }
+
+    /// the instructions of an autopay batch file, as written by the operator.
+    /// Files ending in .csv are read as CSV, others as the autopay json.
+    fn read_instructions(autopay_batch_file: &PathBuf) -> Result<Vec<PayInstruction>, Error> {
+        if autopay_batch_file.extension().map_or(false, |e| e == "csv") {
+            return PayInstruction::read_csv_instructions(autopay_batch_file);
+        }
+        let file = fs::File::open(autopay_batch_file)
+            .with_context(|| format!("cannot open autopay batch file: {:?}", autopay_batch_file))?;
+        let json: Value = serde_json::from_reader(&file)
+            .with_context(|| format!("cannot parse autopay batch file: {:?}", autopay_batch_file))?;
+        let val: Value = json
+            .get("autopay_instructions")
+            .ok_or_else(|| Error::msg(format!("{:?} has no autopay_instructions", autopay_batch_file)))?
+            .to_owned();
+        serde_json::from_value(val)
+            .with_context(|| format!("cannot parse the autopay instructions of {:?}", autopay_batch_file))
+    }
+
+    /// the instructions of a CSV with a header row, one instruction per row. The columns are
+    /// destination, type_of (e.g. PercentOfBalance), value, end_epoch, duration_epochs, and note.
+    /// end_epoch, duration_epochs, and note may be left empty, or out.
+    fn read_csv_instructions(autopay_csv_file: &PathBuf) -> Result<Vec<PayInstruction>, Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(autopay_csv_file)
+            .with_context(|| format!("cannot open autopay csv file: {:?}", autopay_csv_file))?;
+        reader
+            .deserialize()
+            .enumerate()
+            .map(|(i, row)| {
+                row.with_context(|| format!("cannot parse row {} of {:?}", i + 1, autopay_csv_file))
+            })
+            .collect()
+    }
+
+    /// assign uids, epochs, and the move encoding of the values. Every instruction is checked,
+    /// and the problems of all of them are returned together as InstructionErrors.
+    fn transform_instructions(
+        mut inst_vec: Vec<PayInstruction>,
+        current_epoch: Option<u64>,
+        start_uid: Option<u64>,
+        start_offset: Option<i64>,
     ) -> Result<Vec<PayInstruction>, Error> {
-        let file = fs::File::open(autopay_batch_file).expect(&format!(
-            "cannot open autopay batch file: {:?}",
-            autopay_batch_file
-        ));
-        let json: Value = serde_json::from_reader(&file).expect("cannot parse autopay.json");
-        let val: Value = json.get("autopay_instructions").unwrap().to_owned();
-        let inst_vec: Vec<PayInstruction> = serde_json::from_value(val).unwrap();
+        let offset = start_offset.unwrap_or(0);
+        let mut errors = vec![];
+        for (index, inst) in inst_vec.iter_mut().enumerate() {
+            if let Some(end) = inst.end_epoch {
+                if (end as i64) + offset < 0 {
+                    errors.push(InstructionError {
+                        index,
+                        instruction: inst.clone(),
+                        reason: format!("end epoch {} is negative with the start offset {}", end, offset),
+                    });
+                    continue;
+                }
+                inst.end_epoch = Some(offset_epoch(end, offset));
+            }
+        }
+        // durations count from the shifted start
+        let current_epoch = current_epoch.map(|e| offset_epoch(e, offset));
 
         let mut total_pct_of_change: f64 = 0f64;
         let mut total_pct_balance: f64 = 0f64;
@@ -81,16 +204,17 @@ impl PayInstruction {
             .enumerate()
             .map(|(i, mut inst)| {
                 inst.uid = Some(new_uid + i as u64);
+                let mut fail = |inst: &PayInstruction, reason: &str| errors.push(InstructionError {
+                    index: i,
+                    instruction: inst.clone(),
+                    reason: reason.to_owned(),
+                });
 
                 if inst.end_epoch.is_none()
                 && inst.duration_epochs.is_none() {
 
                 if inst.type_of != InstructionType::FixedOnce {
-                      println!(
-                          "Need to set end_epoch, or duration_epoch in instruction: {:?}",
-                          &inst
-                      );
-                      exit(1);
+                      fail(&inst, "Need to set end_epoch, or duration_epoch in instruction");
                   } else {
                     inst.duration_epochs = Some(1);
                   }
@@ -98,17 +222,12 @@ impl PayInstruction {
 
                 if let Some(duration) = inst.duration_epochs {
                     if duration == 0 {
-                      println!("Duration cannot be 0. Instruction: {:?}", &inst);
-                      exit(1);
-                    }
-                    if let Some(current) = current_epoch {
+                      fail(&inst, "Duration cannot be 0");
+                    } else if let Some(current) = current_epoch {
                       inst.end_epoch = Some(duration + current);
                     } else {
-                      println!("If you are setting a duration_epochs instruction, we need the current epoch. Instruction: {:?}", &inst);
-                      exit(1);
+                      fail(&inst, "If you are setting a duration_epochs instruction, we need the current epoch");
                     }
-                } else {
-
                 }
 
                 match inst.type_of {
@@ -138,6 +257,10 @@ impl PayInstruction {
             })
             .collect();
 
+        if !errors.is_empty() {
+            errors.sort_by_key(|e| e.index);
+            return Err(InstructionErrors(errors).into());
+        }
         if (total_pct_of_change < 100f64) && (total_pct_balance < 100f64) {
             Ok(transformed)
         } else {
@@ -145,6 +268,15 @@ impl PayInstruction {
         }
     }
 
+    /// total percent committed by percent of balance and percent of change instructions
+    pub fn percent_totals(instructions: &[PayInstruction]) -> (f64, f64) {
+        instructions.iter().fold((0f64, 0f64), |(balance, change), i| match i.type_of {
+            InstructionType::PercentOfBalance => (balance + i.value, change),
+            InstructionType::PercentOfChange => (balance, change + i.value),
+            _ => (balance, change),
+        })
+    }
+
     /// checks ths instruction against the raw script for correctness.
     pub fn check_instruction_match_tx(&self, script: Script) -> Result<(), Error> {
         let PayInstruction {
@@ -182,6 +314,39 @@ impl PayInstruction {
         Ok(())
     }
 
+    /// checks the instruction against the args of an autopay_create_instruction script function
+    pub fn check_instruction_match_script_function(&self, function: &ScriptFunction) -> Result<(), Error> {
+        if function.function().as_str() != "autopay_create_instruction" {
+            bail!("the signed tx calls {}, not autopay_create_instruction", function.function());
+        }
+        let missing = |field: &str| Error::msg(format!("the instruction has no {}", field));
+        let expected = vec![
+            ("unique id", bcs::to_bytes(&self.uid.ok_or_else(|| missing("uid"))?)?),
+            ("type of transaction", bcs::to_bytes(&self.type_move.ok_or_else(|| missing("type"))?)?),
+            ("destination", bcs::to_bytes(&self.destination)?),
+            ("ending epoch", bcs::to_bytes(&self.end_epoch.ok_or_else(|| missing("end epoch"))?)?),
+            ("value being sent", bcs::to_bytes(&self.value_move.ok_or_else(|| missing("value"))?)?),
+        ];
+        if function.args().len() != expected.len() {
+            bail!("the signed tx has {} args, expected {}", function.args().len(), expected.len());
+        }
+        for ((name, bytes), arg) in expected.iter().zip(function.args()) {
+            if bytes != arg {
+                bail!("the signed tx doesn't have the same {} as the instruction", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// checks the instruction against the payload of a signed tx, a script or a script function
+    pub fn check_instruction_match_payload(&self, payload: &TransactionPayload) -> Result<(), Error> {
+        match payload {
+            TransactionPayload::Script(s) => self.check_instruction_match_tx(s.clone()),
+            TransactionPayload::ScriptFunction(f) => self.check_instruction_match_script_function(f),
+            _ => bail!("the signed tx is neither an autopay script nor a script function"),
+        }
+    }
+
     /// provide text information on the instruction
     pub fn text_instruction(&self) -> String {
       let times = match &self.duration_epochs {
@@ -196,7 +361,7 @@ impl PayInstruction {
             uid = &self.uid.unwrap(),
             percent_balance = *&self.value_move.unwrap() as f64 /100f64,
             times = times,
-            note = &self.note.clone().unwrap(),
+            note = self.note.as_deref().unwrap_or(""),
             epoch_ending = &self.end_epoch.unwrap(),
             destination = &self.destination,
           )
@@ -207,7 +372,7 @@ impl PayInstruction {
               uid = &self.uid.unwrap(),
               percent_balance = *&self.value_move.unwrap() as f64 /100f64,
               times = times,
-              note = &self.note.clone().unwrap(),
+              note = self.note.as_deref().unwrap_or(""),
               epoch_ending = &self.end_epoch.unwrap(),
               destination = &self.destination,
             )
@@ -216,9 +381,9 @@ impl PayInstruction {
             format!(
                 "Instruction {uid}: {note}\nSend {total_val} every day {times} (until epoch {epoch_ending}) to address: {destination}?",
                 uid = &self.uid.unwrap(),
-                total_val = *&self.value_move.unwrap() / 1_000_000, // scaling factor
+                total_val = format_coin(self.value_move.unwrap()),
                 times = times,
-                note = &self.note.clone().unwrap(),
+                note = self.note.as_deref().unwrap_or(""),
                 epoch_ending = &self.end_epoch.unwrap(),
                 destination = &self.destination,
             )
@@ -227,8 +392,8 @@ impl PayInstruction {
           format!(
                 "Instruction {uid}: {note}\nSend {total_val} once to address: {destination}?",
                 uid = &self.uid.unwrap(),
-                note = &self.note.clone().unwrap(),
-                total_val = *&self.value_move.unwrap() / 1_000_000, // scaling factor
+                note = self.note.as_deref().unwrap_or(""),
+                total_val = format_coin(self.value_move.unwrap()),
                 destination = &self.destination,
             )
         }
@@ -261,10 +426,30 @@ pub fn write_batch_file(file_path: PathBuf, vec_instr: Vec<PayInstruction>) -> R
 fn scale_coin(coin_value: f64) -> Option<u64> {
     // the UI for the autopay_batch, allows 2 decimal precision for pecentages: 12.34%
     // multiply by 100 to get the desired decimal precision
-    let scale = coin_value * 1000000 as f64;
+    let scale = coin_value * 10u64.pow(COIN_DECIMALS) as f64;
     Some(scale as u64)
 }
 
+/// Micro units of the coin as whole coins, e.g. 5500000 is "5.5 GAS"
+pub fn format_coin(micro_units: u64) -> String {
+    let scale = 10u64.pow(COIN_DECIMALS);
+    let fraction = micro_units % scale;
+    if fraction == 0 {
+        return format!("{} {}", micro_units / scale, COIN_NAME);
+    }
+    let decimals = format!("{:0width$}", fraction, width = COIN_DECIMALS as usize);
+    format!("{}.{} {}", micro_units / scale, decimals.trim_end_matches('0'), COIN_NAME)
+}
+
+/// An epoch shifted by the autopay start offset, at zero if the offset is before it
+pub fn offset_epoch(epoch: u64, offset: i64) -> u64 {
+    if offset < 0 {
+        epoch.saturating_sub(offset.unsigned_abs())
+    } else {
+        epoch.saturating_add(offset as u64)
+    }
+}
+
 fn scale_percent(fract_percent: f64) -> Option<u64> {
     // the UI for the autopay_batch, allows 2 decimal precision for pecentages: 12.34%
     // multiply by 100 to get the desired decimal precision
@@ -283,13 +468,13 @@ fn scale_percent(fract_percent: f64) -> Option<u64> {
 #[test]
 fn parse_file() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
 }
 
 #[test]
 fn parse_pct_balance_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let first = &inst[0];
     
     assert_eq!(first.uid, Some(1));
@@ -304,7 +489,7 @@ fn parse_pct_balance_type() {
 #[test]
 fn parse_pct_change_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let second = &inst[1];
     
     assert_eq!(second.uid, Some(2));
@@ -320,7 +505,7 @@ fn parse_pct_change_type() {
 #[test]
 fn parse_fixed_recurr_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let third = &inst[2];
     
     assert_eq!(third.uid, Some(3));
@@ -335,7 +520,7 @@ fn parse_fixed_recurr_type() {
 #[test]
 fn parse_fixed_once_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let fourth = &inst[3];
     
     assert_eq!(fourth.uid, Some(4));
@@ -350,7 +535,7 @@ fn parse_fixed_once_type() {
 #[test]
 fn parse_pct_balance_end_epoch_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let fifth = &inst[4];
     
     assert_eq!(fifth.uid, Some(5));
@@ -365,7 +550,7 @@ fn parse_pct_balance_end_epoch_type() {
 #[test]
 fn parse_pct_change_end_epoch_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let sixth = &inst[5];
     
     assert_eq!(sixth.uid, Some(6));
@@ -381,7 +566,7 @@ fn parse_pct_change_end_epoch_type() {
 #[test]
 fn parse_fixed_recurr_end_epoch_type() {
     let path = ol_fixtures::get_demo_autopay_json().1;
-    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None).unwrap();
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
     let seventh = &inst[6];
     
     assert_eq!(seventh.uid, Some(7));
@@ -394,3 +579,159 @@ fn parse_fixed_recurr_end_epoch_type() {
     assert_eq!(seventh.value_move.unwrap(), 5000000u64);
 
 }
+
+#[test]
+fn percent_totals_of_demo() {
+    let path = ol_fixtures::get_demo_autopay_json().1;
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
+    let (balance, change) = PayInstruction::percent_totals(&inst);
+    let expected_balance: f64 = inst
+        .iter()
+        .filter(|i| i.type_of == InstructionType::PercentOfBalance)
+        .map(|i| i.value)
+        .sum();
+    assert!((balance - expected_balance).abs() < f64::EPSILON);
+    assert!(change < 100f64);
+}
+
+#[test]
+fn parse_dir_merges_files() {
+    let path = ol_fixtures::get_demo_autopay_json().1;
+    let single = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
+
+    let dir = std::env::temp_dir().join("autopay_dir_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(&path, dir.join("b.json")).unwrap();
+    fs::copy(&path, dir.join("a.json")).unwrap();
+    fs::write(dir.join("notes.txt"), "not autopay").unwrap();
+
+    let (inst, counts) = PayInstruction::parse_autopay_dir(&dir, Some(0), None, None).unwrap();
+    assert_eq!(inst.len(), 2 * single.len());
+    assert_eq!(inst.last().unwrap().uid, Some(2 * single.len() as u64));
+    assert_eq!(counts, vec![(dir.join("a.json"), single.len()), (dir.join("b.json"), single.len())]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn format_coin_decimals() {
+    assert_eq!(format_coin(5_000_000), "5 GAS");
+    assert_eq!(format_coin(5_500_000), "5.5 GAS");
+    assert_eq!(format_coin(1), "0.000001 GAS");
+}
+
+#[test]
+fn parse_csv_instructions() {
+    let dir = std::env::temp_dir().join("autopay_csv_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("autopay.csv");
+    fs::write(
+        &path,
+        "destination,type_of,value,end_epoch,duration_epochs,note\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfBalance,10.5,,10,rent\n\
+        88E74DFED34420F2AD8032148280A84B,FixedOnce,5,,,\n",
+    )
+    .unwrap();
+
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
+    assert_eq!(inst.len(), 2);
+    assert_eq!(inst[0].type_of, InstructionType::PercentOfBalance);
+    assert_eq!(inst[0].note, Some("rent".to_owned()));
+    assert_eq!(inst[0].end_epoch, Some(10));
+    assert_eq!(inst[1].type_of, InstructionType::FixedOnce);
+    assert_eq!(inst[1].uid, Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_instruction_without_note() {
+    let dir = std::env::temp_dir().join("autopay_csv_note_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("autopay.csv");
+    fs::write(
+        &path,
+        "destination,type_of,value,end_epoch,duration_epochs,note\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfChange,12,20,,\n",
+    )
+    .unwrap();
+
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap();
+    assert!(inst[0].note.as_deref().unwrap_or("").is_empty());
+    // the review prints it without a note
+    assert!(inst[0].text_instruction().starts_with("Instruction 1: \n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_bad_row_is_an_error() {
+    let dir = std::env::temp_dir().join("autopay_csv_bad_row_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("autopay.csv");
+    fs::write(
+        &path,
+        "destination,type_of,value,end_epoch,duration_epochs,note\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfChange,12,20,,rent\n\
+        88E74DFED34420F2AD8032148280A84B,NotAType,12,20,,rent\n",
+    )
+    .unwrap();
+
+    let e = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap_err();
+    assert!(format!("{:?}", e).contains("cannot parse row 2"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parse_with_start_offset() {
+    let dir = std::env::temp_dir().join("autopay_offset_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("autopay.csv");
+    fs::write(
+        &path,
+        "destination,type_of,value,end_epoch,duration_epochs,note\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfBalance,10,,10,rent\n\
+        88E74DFED34420F2AD8032148280A84B,FixedRecurring,5,20,,bills\n",
+    )
+    .unwrap();
+
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(5), None, Some(10)).unwrap();
+    assert_eq!(inst[0].end_epoch, Some(25));
+    assert_eq!(inst[1].end_epoch, Some(30));
+
+    // the start clamps at zero, an end epoch can't
+    let inst = PayInstruction::parse_autopay_instructions(&path, Some(5), None, Some(-20)).unwrap();
+    assert_eq!(inst[0].end_epoch, Some(10));
+    assert_eq!(inst[1].end_epoch, Some(0));
+    assert!(PayInstruction::parse_autopay_instructions(&path, Some(5), None, Some(-21)).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn transform_collects_all_errors() {
+    let dir = std::env::temp_dir().join("autopay_transform_errors_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("autopay.csv");
+    fs::write(
+        &path,
+        "destination,type_of,value,end_epoch,duration_epochs,note\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfBalance,10,,,rent\n\
+        88E74DFED34420F2AD8032148280A84B,PercentOfChange,10,,0,bills\n\
+        88E74DFED34420F2AD8032148280A84B,FixedRecurring,5,20,,food\n",
+    )
+    .unwrap();
+
+    let e = PayInstruction::parse_autopay_instructions(&path, Some(0), None, None).unwrap_err();
+    let errors = &e.downcast_ref::<InstructionErrors>().unwrap().0;
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].index, 0);
+    assert_eq!(errors[1].index, 1);
+
+    // a duration needs the current epoch, reported instead of exiting
+    let e = PayInstruction::parse_autopay_instructions(&path, None, None, None).unwrap_err();
+    assert_eq!(e.downcast_ref::<InstructionErrors>().unwrap().0.len(), 2);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -60,6 +60,19 @@ impl WalletLibrary {
         }
     }
 
+    //////// 0L ////////
+    /// Constructor that instantiates a new WalletLibrary from a Mnemonic and a BIP39 passphrase,
+    /// which is appended to the salt. An empty passphrase is the same as new_from_mnemonic.
+    pub fn new_from_mnemonic_with_passphrase(mnemonic: Mnemonic, passphrase: &str) -> Self {
+        let seed = Seed::new(&mnemonic, &format!("{}{}", SALT_0L, passphrase));
+        WalletLibrary {
+            mnemonic,
+            key_factory: KeyFactory::new(&seed).unwrap(),
+            addr_map: HashMap::new(),
+            key_leaf: ChildNumber(0),
+        }
+    }
+
     /// Function that returns the string representation of the WalletLibrary Mnemonic
     /// NOTE: This is not secure, and in general the mnemonic should be stored in encrypted format
     pub fn mnemonic(&self) -> String {